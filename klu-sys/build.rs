@@ -4,6 +4,7 @@ fn main() {
     if std::env::var_os("CARGO_FEATURE_DYNAMIC").is_some() {
         println!("cargo:rustc-link-lib=klu");
     } else {
+        build_layout();
         let src_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("vendor");
         build_suitesparse_config(&src_dir);
         for long in [true, false] {
@@ -19,6 +20,26 @@ fn main() {
     }
 }
 
+/// The sizes and field offsets of the KLU objects computed from the vendored headers.
+/// Only the layout tests reference these symbols so they are not linked into other binaries.
+/// The headers of a system KLU are not known, so the layout is only verified for the vendored
+/// build.
+fn build_layout() {
+    let src_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("vendor");
+    cc::Build::new()
+        .file(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("src")
+                .join("layout.c"),
+        )
+        .include(src_dir.join("KLU").join("Include"))
+        .include(src_dir.join("AMD").join("Include"))
+        .include(src_dir.join("COLAMD").join("Include"))
+        .include(src_dir.join("BTF").join("Include"))
+        .include(src_dir.join("SuiteSparse_config"))
+        .compile("klu_sys_layout");
+}
+
 fn build_suitesparse_config(src_dir: &Path) {
     cc::Build::new()
         .file(
@@ -64,7 +85,7 @@ fn build_amd(src_dir: &Path, long: bool) {
     ];
 
     for obj in amd_objects.iter() {
-        builder.file(&amd_src_dir.join(format!("{obj}.c")));
+        builder.file(amd_src_dir.join(format!("{obj}.c")));
     }
 
     builder.compile(if long { "amdl" } else { "amd" });
@@ -89,7 +110,7 @@ fn build_btf(src_dir: &Path, long: bool) {
 
     let btf_objects = ["btf_maxtrans", "btf_order", "btf_strongcomp"];
     for obj in btf_objects.iter() {
-        builder.file(&btf_src_dir.join(format!("{}.c", obj)));
+        builder.file(btf_src_dir.join(format!("{}.c", obj)));
     }
 
     builder.compile(if long { "btfl" } else { "btf" });
@@ -140,6 +161,7 @@ fn build_klu(src_dir: &Path, long: bool, complex: bool) {
         "",
         "_diagnostics",
         "_dump",
+        "_extract",
         "_factor",
         "_free_numeric",
         "_kernel",
@@ -0,0 +1,34 @@
+/* Sizes and field offsets of the KLU objects used by the layout tests of klu-sys.
+ * The values are computed by the C compiler from the vendored headers so the Rust
+ * definitions can be checked against them. */
+
+#include <stddef.h>
+#include "klu.h"
+
+#define SYMBOLIC_LAYOUT(T)                                                     \
+    {                                                                          \
+        sizeof (T), offsetof (T, symmetry), offsetof (T, est_flops),           \
+        offsetof (T, lnz), offsetof (T, unz), offsetof (T, Lnz),               \
+        offsetof (T, n), offsetof (T, nz), offsetof (T, P), offsetof (T, Q),   \
+        offsetof (T, R), offsetof (T, nzoff), offsetof (T, nblocks),           \
+        offsetof (T, maxblock), offsetof (T, ordering), offsetof (T, do_btf),  \
+        offsetof (T, structural_rank)                                          \
+    }
+
+#define NUMERIC_LAYOUT(T)                                                      \
+    {                                                                          \
+        sizeof (T), offsetof (T, n), offsetof (T, nblocks), offsetof (T, lnz), \
+        offsetof (T, unz), offsetof (T, max_lnz_block),                        \
+        offsetof (T, max_unz_block), offsetof (T, Pnum), offsetof (T, Pinv),   \
+        offsetof (T, Lip), offsetof (T, Uip), offsetof (T, Llen),              \
+        offsetof (T, Ulen), offsetof (T, LUbx), offsetof (T, LUsize),          \
+        offsetof (T, Udiag), offsetof (T, Rs), offsetof (T, worksize),         \
+        offsetof (T, Work), offsetof (T, Xwork), offsetof (T, Iwork),          \
+        offsetof (T, Offp), offsetof (T, Offi), offsetof (T, Offx),            \
+        offsetof (T, nzoff)                                                    \
+    }
+
+const size_t klu_sys_symbolic_layout [] = SYMBOLIC_LAYOUT (klu_symbolic) ;
+const size_t klu_sys_l_symbolic_layout [] = SYMBOLIC_LAYOUT (klu_l_symbolic) ;
+const size_t klu_sys_numeric_layout [] = NUMERIC_LAYOUT (klu_numeric) ;
+const size_t klu_sys_l_numeric_layout [] = NUMERIC_LAYOUT (klu_l_numeric) ;
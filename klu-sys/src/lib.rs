@@ -2,29 +2,67 @@ use std::os::raw::c_ulong;
 #[allow(non_camel_case_types)]
 pub type size_t = c_ulong;
 
+/// `klu_symbolic` (`Int = i32`) and `klu_l_symbolic` (`Int = i64`). KLU compiles both
+/// versions from the same source with a different `Int` type.
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
-pub struct KluSymbolic {
-    _data: [f64; 0],
+#[allow(non_snake_case)]
+pub struct KluSymbolicT<Int> {
+    pub symmetry: f64,
+    pub est_flops: f64,
+    pub lnz: f64,
+    pub unz: f64,
+    pub Lnz: *mut f64,
+    pub n: Int,
+    pub nz: Int,
+    pub P: *mut Int,
+    pub Q: *mut Int,
+    pub R: *mut Int,
+    pub nzoff: Int,
+    pub nblocks: Int,
+    pub maxblock: Int,
+    pub ordering: Int,
+    pub do_btf: Int,
+    pub structural_rank: Int,
 }
 
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct KluLSymbolic {
-    _data: [f64; 0],
-}
+pub type KluSymbolic = KluSymbolicT<i32>;
+pub type KluLSymbolic = KluSymbolicT<i64>;
 
+/// `klu_numeric` (`Int = i32`) and `klu_l_numeric` (`Int = i64`). KLU compiles both
+/// versions from the same source with a different `Int` type.
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
-pub struct KluNumeric {
-    _data: [f64; 0],
+#[allow(non_snake_case)]
+pub struct KluNumericT<Int> {
+    pub n: Int,
+    pub nblocks: Int,
+    pub lnz: Int,
+    pub unz: Int,
+    pub max_lnz_block: Int,
+    pub max_unz_block: Int,
+    pub Pnum: *mut Int,
+    pub Pinv: *mut Int,
+    pub Lip: *mut Int,
+    pub Uip: *mut Int,
+    pub Llen: *mut Int,
+    pub Ulen: *mut Int,
+    pub LUbx: *mut *mut ::std::os::raw::c_void,
+    pub LUsize: *mut size_t,
+    pub Udiag: *mut ::std::os::raw::c_void,
+    pub Rs: *mut f64,
+    pub worksize: size_t,
+    pub Work: *mut ::std::os::raw::c_void,
+    pub Xwork: *mut ::std::os::raw::c_void,
+    pub Iwork: *mut Int,
+    pub Offp: *mut Int,
+    pub Offi: *mut Int,
+    pub Offx: *mut ::std::os::raw::c_void,
+    pub nzoff: Int,
 }
 
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct KluLNumeric {
-    _data: [f64; 0],
-}
+pub type KluNumeric = KluNumericT<i32>;
+pub type KluLNumeric = KluNumericT<i64>;
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
@@ -556,3 +594,7 @@ extern "C" {
         arg19: *mut KluLCommon,
     ) -> i64;
 }
+
+// the layout is computed from the vendored headers which are not used with `dynamic`
+#[cfg(all(test, not(feature = "dynamic")))]
+mod test;
@@ -0,0 +1,88 @@
+use std::mem::{offset_of, size_of};
+
+use crate::{size_t, KluLNumeric, KluLSymbolic, KluNumeric, KluSymbolic};
+
+extern "C" {
+    static klu_sys_symbolic_layout: [size_t; 17];
+    static klu_sys_l_symbolic_layout: [size_t; 17];
+    static klu_sys_numeric_layout: [size_t; 25];
+    static klu_sys_l_numeric_layout: [size_t; 25];
+}
+
+macro_rules! layout {
+    ($ty: ty, $($field: ident),*) => {
+        [size_of::<$ty>(), $(offset_of!($ty, $field)),*].map(|val| val as size_t)
+    };
+}
+
+macro_rules! symbolic_layout {
+    ($ty: ty) => {
+        layout!(
+            $ty,
+            symmetry,
+            est_flops,
+            lnz,
+            unz,
+            Lnz,
+            n,
+            nz,
+            P,
+            Q,
+            R,
+            nzoff,
+            nblocks,
+            maxblock,
+            ordering,
+            do_btf,
+            structural_rank
+        )
+    };
+}
+
+macro_rules! numeric_layout {
+    ($ty: ty) => {
+        layout!(
+            $ty,
+            n,
+            nblocks,
+            lnz,
+            unz,
+            max_lnz_block,
+            max_unz_block,
+            Pnum,
+            Pinv,
+            Lip,
+            Uip,
+            Llen,
+            Ulen,
+            LUbx,
+            LUsize,
+            Udiag,
+            Rs,
+            worksize,
+            Work,
+            Xwork,
+            Iwork,
+            Offp,
+            Offi,
+            Offx,
+            nzoff
+        )
+    };
+}
+
+#[test]
+fn symbolic_layout() {
+    unsafe {
+        assert_eq!(symbolic_layout!(KluSymbolic), klu_sys_symbolic_layout);
+        assert_eq!(symbolic_layout!(KluLSymbolic), klu_sys_l_symbolic_layout);
+    }
+}
+
+#[test]
+fn numeric_layout() {
+    unsafe {
+        assert_eq!(numeric_layout!(KluNumeric), klu_sys_numeric_layout);
+        assert_eq!(numeric_layout!(KluLNumeric), klu_sys_l_numeric_layout);
+    }
+}
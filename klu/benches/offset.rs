@@ -0,0 +1,34 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use klu_rs::{KluMatrixBuilder, KluSettings};
+
+const DIM: i32 = 4096;
+
+/// Looks up every entry of a column with `density` entries (like the ground node of a circuit)
+fn offset_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("offset");
+    for density in [4, 32, 256, 2048] {
+        let mut builder = KluMatrixBuilder::new(DIM);
+        for col in 0..DIM {
+            builder.add_entry(col, col);
+        }
+        let stride = DIM / density;
+        let rows: Vec<_> = (0..density).map(|i| i * stride).collect();
+        for &row in &rows {
+            builder.add_entry(0, row);
+            builder.add_entry(row, 0);
+        }
+        let spec = builder.finish(KluSettings::new()).unwrap();
+
+        group.bench_with_input(BenchmarkId::from_parameter(density), &rows, |b, rows| {
+            b.iter(|| {
+                for &row in rows {
+                    black_box(spec.offset(black_box(0), row));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, offset_lookup);
+criterion_main!(benches);
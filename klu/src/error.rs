@@ -0,0 +1,151 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+/// An error reported by KLU (or by the safe wrapper around it).
+///
+/// KLU reports failures by writing a status code into `KluCommon`. These status codes are mapped
+/// to this enum so that callers can recover from them instead of aborting the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KluError {
+    /// KLU ran out of memory (`KLU_OUT_OF_MEMORY`)
+    OutOfMemory,
+    /// The input to KLU was invalid, for example a malformed sparsity pattern (`KLU_INVALID`)
+    Invalid,
+    /// An integer overflow occurred inside KLU, the problem is too large (`KLU_TOO_LARGE`)
+    TooLarge,
+    /// The matrix is singular (`KLU_SINGULAR`). `column` is the first column (in the original
+    /// matrix) where a zero pivot was encountered.
+    Singular { column: usize },
+    /// KLU returned a status code that is not known to this crate
+    Unknown(i64),
+    /// A KLU call reported failure but left the status at `KLU_OK`
+    CallFailed,
+    /// The sparsity pattern was rejected before it was passed to KLU
+    InvalidPattern(PatternError),
+}
+
+/// Describes why a sparsity pattern is invalid. Positions refer to the `(column, row)` of the
+/// entry in the matrix (also for specs constructed from CSR arrays).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternError {
+    /// The matrix has no columns (KLU requires at least one)
+    Empty,
+    /// The dimension or the number of entries exceed the largest value of the index type
+    TooLarge { dim: usize, entries: usize },
+    /// The entry at `column` and `row` is outside of the square matrix of dimension `dim`
+    EntryOutOfBounds { column: i64, row: i64, dim: usize },
+    /// The entry at `column` and `row` is present multiple times
+    DuplicateEntry { column: usize, row: usize },
+    /// The compressed arrays contain `found` offsets instead of `expected` (`dim + 1`)
+    OffsetCount { expected: usize, found: usize },
+    /// The offset at `index` does not start at zero, decreases or does not end at the number of
+    /// entries
+    InvalidOffset { index: usize },
+}
+
+/// A setting of [`KluSettings`](crate::KluSettings) was given a value outside of its valid range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SettingError {
+    /// The name of the setting
+    pub setting: &'static str,
+    /// The rejected value
+    pub value: f64,
+    /// The range of valid values
+    pub expected: &'static str,
+}
+
+impl Display for SettingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} must be {} but was {}",
+            self.setting, self.expected, self.value
+        )
+    }
+}
+
+impl Error for SettingError {}
+
+impl PatternError {
+    /// Swaps the column and row of the offending entry (for patterns that store the transpose)
+    pub(crate) fn transpose(self) -> Self {
+        match self {
+            PatternError::EntryOutOfBounds { column, row, dim } => PatternError::EntryOutOfBounds {
+                column: row,
+                row: column,
+                dim,
+            },
+            PatternError::DuplicateEntry { column, row } => PatternError::DuplicateEntry {
+                column: row,
+                row: column,
+            },
+            err => err,
+        }
+    }
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::Empty => write!(f, "the matrix has no columns"),
+            PatternError::TooLarge { dim, entries } => write!(
+                f,
+                "{dim} columns with {entries} entries exceed the range of the index type"
+            ),
+            PatternError::EntryOutOfBounds { column, row, dim } => write!(
+                f,
+                "entry at column {column}, row {row} is outside of the {dim}x{dim} matrix"
+            ),
+            PatternError::DuplicateEntry { column, row } => {
+                write!(f, "duplicate entry at column {column}, row {row}")
+            }
+            PatternError::OffsetCount { expected, found } => {
+                write!(f, "expected {expected} column offsets but found {found}")
+            }
+            PatternError::InvalidOffset { index } => write!(f, "invalid column offset {index}"),
+        }
+    }
+}
+
+impl Error for PatternError {}
+
+impl From<PatternError> for KluError {
+    fn from(err: PatternError) -> Self {
+        KluError::InvalidPattern(err)
+    }
+}
+
+impl Display for KluError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            KluError::OutOfMemory => write!(f, "KLU error: OUT OF MEMORY"),
+            KluError::Invalid => write!(f, "KLU error: INVALID"),
+            KluError::TooLarge => write!(f, "KLU error: TOO LARGE"),
+            KluError::Singular { column } => {
+                write!(f, "KLU error: matrix is singular (column {column})")
+            }
+            KluError::Unknown(code) => write!(f, "KLU failed with unknown errorcode {code}"),
+            KluError::CallFailed => write!(f, "KLU call failed without an error status"),
+            KluError::InvalidPattern(err) => write!(f, "invalid sparsity pattern: {err}"),
+        }
+    }
+}
+
+impl Error for KluError {}
+
+impl KluError {
+    /// Maps a KLU status code to a result. `singular_col` is only used if `status` is
+    /// `KLU_SINGULAR`.
+    pub(crate) fn from_status(status: i64, singular_col: i64) -> Result<(), KluError> {
+        match status {
+            0 => Ok(()),
+            1 => Err(KluError::Singular {
+                column: singular_col as usize,
+            }),
+            -2 => Err(KluError::OutOfMemory),
+            -3 => Err(KluError::Invalid),
+            -4 => Err(KluError::TooLarge),
+            code => Err(KluError::Unknown(code)),
+        }
+    }
+}
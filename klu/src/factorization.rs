@@ -0,0 +1,423 @@
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use crate::{
+    FactorOutcome, FixedKluMatrix, KluData, KluError, KluIndex, KluMatrixSpec, KluSettings,
+    KluSpecRef,
+};
+
+/// The result of [`Factorization::solve_refined`] and
+/// [`solve_refined_with_bound`](Factorization::solve_refined_with_bound)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RefinementStats {
+    /// The number of refinement steps that were applied to the initial solution
+    pub steps: usize,
+    /// The componentwise backward error `max_i |b - Ax|_i / (|A||x| + |b|)_i` of the returned
+    /// solution. NaN if the solution contains infinite or NaN values (for example because the
+    /// matrix is singular).
+    pub backward_error: f64,
+    /// The bound on the relative forward error computed by
+    /// [`forward_error_bound`](Factorization::forward_error_bound). Only filled by
+    /// [`solve_refined_with_bound`](Factorization::solve_refined_with_bound) and `None` if the
+    /// matrix is too badly conditioned to give a meaningful bound.
+    pub forward_error_bound: Option<f64>,
+}
+
+/// The LU factorization of a [`FixedKluMatrix`] as returned by
+/// [`lu_factorize`](FixedKluMatrix::lu_factorize).
+///
+/// The factorization stores a shared reference to the matrix, but it is created from the mutable
+/// borrow taken by [`lu_factorize`](FixedKluMatrix::lu_factorize) so the matrix values can not be
+/// modified while the factorization is alive. To solve a system with new values, drop the
+/// factorization, write the new values and call [`lu_factorize`](FixedKluMatrix::lu_factorize)
+/// again.
+///
+/// Writing a value while the factorization is alive does not compile:
+///
+/// ```compile_fail
+/// use klu_rs::{KluMatrixSpec, KluSettings, RefactorPolicy};
+///
+/// let spec = KluMatrixSpec::new(&[vec![0]], KluSettings::new()).unwrap();
+/// let mut matrix = spec.create_matrix::<f64>().unwrap();
+/// matrix[(0, 0)].set(2.0);
+/// let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+/// matrix[(0, 0)].set(4.0);
+/// let mut rhs = [2.0];
+/// lu.solve_linear_system(&mut rhs).unwrap();
+/// ```
+///
+/// Neither does solving without factorizing the matrix first:
+///
+/// ```compile_fail
+/// use klu_rs::{KluMatrixSpec, KluSettings};
+///
+/// let spec = KluMatrixSpec::new(&[vec![0]], KluSettings::new()).unwrap();
+/// let matrix = spec.create_matrix::<f64>().unwrap();
+/// matrix[(0, 0)].set(2.0);
+/// let mut rhs = [2.0];
+/// matrix.solve_linear_system(&mut rhs).unwrap();
+/// ```
+pub struct Factorization<'a, I: KluIndex, D: KluData, S: KluSpecRef<I> = Rc<KluMatrixSpec<I>>> {
+    matrix: &'a FixedKluMatrix<I, D, S>,
+    klu_numeric: NonNull<I::KluNumeric>,
+    outcome: FactorOutcome,
+}
+
+impl<'a, I: KluIndex, D: KluData, S: KluSpecRef<I>> Factorization<'a, I, D, S> {
+    pub(crate) fn new(
+        matrix: &'a FixedKluMatrix<I, D, S>,
+        klu_numeric: NonNull<I::KluNumeric>,
+        outcome: FactorOutcome,
+    ) -> Self {
+        Self {
+            matrix,
+            klu_numeric,
+            outcome,
+        }
+    }
+
+    /// Whether the matrix was refactored or fully factorized (and why).
+    ///
+    /// If this is [`FactorOutcome::Singular`] the factorization contains a zero pivot and solving
+    /// with it produces infinite or NaN values.
+    pub fn outcome(&self) -> FactorOutcome {
+        self.outcome
+    }
+
+    /// The spec that describes the sparsity pattern of the factorized matrix
+    pub fn spec(&self) -> &S {
+        self.matrix.spec()
+    }
+
+    /// The settings that receive the KLU status of the factorized matrix,
+    /// see [`FixedKluMatrix::settings`]
+    pub fn settings(&self) -> &KluSettings<I> {
+        self.matrix.settings()
+    }
+
+    pub(crate) fn klu_numeric(&self) -> NonNull<I::KluNumeric> {
+        self.klu_numeric
+    }
+
+    /// solves the linear system `Ax=b`. The `b` vector is read from `rhs` at the beginning of the
+    /// function. After the functin completes `x` was written into `rhs`
+    pub fn solve_linear_system(&self, rhs: &mut [D]) -> Result<(), KluError> {
+        self.solve_untransposed(rhs, rhs.len(), 1)
+    }
+
+    /// solves the linear system `A^T x=b` The `b` vector is read from `rhs` at the beginning of the
+    /// function. After the functin completes `x` was written into `rhs`
+    pub fn solve_linear_tranose_system(&self, rhs: &mut [D]) -> Result<(), KluError> {
+        self.solve_transposed(rhs, rhs.len(), 1, false)
+    }
+
+    /// solves the linear system `A^H x=b` (where `A^H` is the conjugate transpose of `A`).
+    /// The `b` vector is read from `rhs` at the beginning of the function. After the functin
+    /// completes `x` was written into `rhs`.
+    /// For real matrices this is equivalent to [`solve_linear_tranose_system`].
+    ///
+    /// [`solve_linear_tranose_system`]: Self::solve_linear_tranose_system
+    pub fn solve_conjugate_transpose_system(&self, rhs: &mut [D]) -> Result<(), KluError> {
+        self.solve_transposed(rhs, rhs.len(), 1, true)
+    }
+
+    /// solves the linear system `AX=B` for `nrhs` right hand sides at once. `B` is read from `rhs`
+    /// in column major order (so each right hand side is stored contiguously) at the beginning of
+    /// the function. After the function completes `X` was written into `rhs`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::Invalid`] if `rhs.len()` is not `nrhs` times the matrix dimension or
+    /// if `nrhs` does not fit into the index type.
+    pub fn solve_multiple(&self, rhs: &mut [D], nrhs: usize) -> Result<(), KluError> {
+        let dim = self.check_multiple(rhs, nrhs)?;
+        self.solve_untransposed(rhs, dim, nrhs)
+    }
+
+    /// solves the linear system `A^T X=B` for `nrhs` right hand sides at once.
+    /// See [`solve_multiple`](Self::solve_multiple) for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::Invalid`] if `rhs.len()` is not `nrhs` times the matrix dimension or
+    /// if `nrhs` does not fit into the index type.
+    pub fn solve_multiple_transpose(&self, rhs: &mut [D], nrhs: usize) -> Result<(), KluError> {
+        let dim = self.check_multiple(rhs, nrhs)?;
+        self.solve_transposed(rhs, dim, nrhs, false)
+    }
+
+    /// solves the linear system `A^H X=B` for `nrhs` right hand sides at once.
+    /// See [`solve_multiple`](Self::solve_multiple) for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::Invalid`] if `rhs.len()` is not `nrhs` times the matrix dimension or
+    /// if `nrhs` does not fit into the index type.
+    pub fn solve_multiple_conjugate_transpose(
+        &self,
+        rhs: &mut [D],
+        nrhs: usize,
+    ) -> Result<(), KluError> {
+        let dim = self.check_multiple(rhs, nrhs)?;
+        self.solve_transposed(rhs, dim, nrhs, true)
+    }
+
+    /// solves the linear system `Ax=b` like [`solve_linear_system`](Self::solve_linear_system)
+    /// and improves the solution with iterative refinement. Each step computes the residual
+    /// `r = b - Ax` with the stored matrix values, solves `Ad=r` with this factorization and
+    /// updates `x += d`.
+    ///
+    /// Refinement stops once the componentwise backward error is at most `tol`, `max_iters`
+    /// steps were performed, a step does not halve the backward error anymore or the backward
+    /// error is NaN.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::Invalid`] if `rhs.len()` is not the matrix dimension.
+    pub fn solve_refined(
+        &self,
+        rhs: &mut [D],
+        max_iters: usize,
+        tol: f64,
+    ) -> Result<RefinementStats, KluError> {
+        let dim = self.spec().dim();
+        if rhs.len() != dim {
+            return Err(KluError::Invalid);
+        }
+        let b = rhs.to_vec();
+        let x = rhs;
+        self.solve_linear_system(x)?;
+
+        let mut residual = vec![D::zero(); dim];
+        let mut scale = vec![0f64; dim];
+        let mut steps = 0;
+        let mut last_error = f64::INFINITY;
+        let backward_error = loop {
+            self.matrix.mul_vec(x, &mut residual)?;
+            for (residual, &b) in residual.iter_mut().zip(&b) {
+                *residual = b - *residual;
+            }
+            for (scale, b) in scale.iter_mut().zip(&b) {
+                *scale = b.abs();
+            }
+            self.matrix.abs_gaxpy(x, &mut scale);
+
+            let error = residual
+                .iter()
+                .zip(&scale)
+                .map(|(residual, &scale)| {
+                    // avoid 0/0 for exact zero rows
+                    let residual = residual.abs();
+                    if residual == 0.0 {
+                        0.0
+                    } else {
+                        residual / scale
+                    }
+                })
+                // unlike `f64::max` this propagates NaN
+                .fold(0.0, |max, error| {
+                    if error.is_nan() || error > max {
+                        error
+                    } else {
+                        max
+                    }
+                });
+
+            if error.is_nan() || error <= tol || steps == max_iters || error > 0.5 * last_error {
+                break error;
+            }
+
+            self.solve_linear_system(&mut residual)?;
+            for (x, &correction) in x.iter_mut().zip(&residual) {
+                *x += correction;
+            }
+            steps += 1;
+            last_error = error;
+        };
+
+        Ok(RefinementStats {
+            steps,
+            backward_error,
+            forward_error_bound: None,
+        })
+    }
+
+    /// solves the linear system `Ax=b` with iterative refinement like
+    /// [`solve_refined`](Self::solve_refined) and additionally fills
+    /// [`RefinementStats::forward_error_bound`] with the
+    /// [`forward_error_bound`](Self::forward_error_bound) of the returned solution.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::Invalid`] if `rhs.len()` is not the matrix dimension or the error
+    /// reported by `klu_condest`, for example [`KluError::Singular`] if the factorization
+    /// contains a zero pivot.
+    pub fn solve_refined_with_bound(
+        &self,
+        rhs: &mut [D],
+        max_iters: usize,
+        tol: f64,
+    ) -> Result<RefinementStats, KluError> {
+        let stats = self.solve_refined(rhs, max_iters, tol)?;
+        Ok(RefinementStats {
+            forward_error_bound: self.forward_error_bound(stats.backward_error)?,
+            ..stats
+        })
+    }
+
+    /// Estimates a bound on the relative forward error `||x - x_exact|| / ||x||` of a solution
+    /// with the given componentwise `backward_error` (see [`solve_refined`](Self::solve_refined))
+    /// from the condition estimate of [`condest`](Self::condest) and the rounding error of the
+    /// residual. Computing the condition estimate requires additional solves.
+    ///
+    /// # Returns
+    ///
+    /// The bound or `None` if the matrix is too badly conditioned to give a meaningful bound
+    ///
+    /// # Errors
+    ///
+    /// Returns the error reported by `klu_condest`, for example [`KluError::Singular`] if the
+    /// factorization contains a zero pivot.
+    pub fn forward_error_bound(&self, backward_error: f64) -> Result<Option<f64>, KluError> {
+        // the computed residual is itself only accurate up to the rounding error of the product
+        let rounding = (self.matrix.max_row_entries() + 1) as f64 * f64::EPSILON;
+        let perturbation = self.condest()? * (backward_error + rounding);
+        Ok((perturbation < 1.0).then(|| 2.0 * perturbation / (1.0 - perturbation)))
+    }
+
+    /// Checks that `rhs` contains `nrhs` right hand sides and returns the matrix dimension
+    fn check_multiple(&self, rhs: &[D], nrhs: usize) -> Result<usize, KluError> {
+        let dim = self.spec().dim();
+        if nrhs > I::MAX || dim.checked_mul(nrhs) != Some(rhs.len()) {
+            return Err(KluError::Invalid);
+        }
+        Ok(dim)
+    }
+
+    /// Solves `AX=B` where the spec might store `A^T` (see [`KluMatrixSpec::from_csr`])
+    fn solve_untransposed(&self, rhs: &mut [D], ldim: usize, nrhs: usize) -> Result<(), KluError> {
+        if self.spec().transposed {
+            self.tsolve(rhs, ldim, nrhs, false)
+        } else {
+            self.solve(rhs, ldim, nrhs)
+        }
+    }
+
+    /// Solves `A^T X=B` (or `A^H X=B` if `conjugate` is set) where the spec might store `A^T`
+    /// (see [`KluMatrixSpec::from_csr`])
+    fn solve_transposed(
+        &self,
+        rhs: &mut [D],
+        ldim: usize,
+        nrhs: usize,
+        conjugate: bool,
+    ) -> Result<(), KluError> {
+        if !self.spec().transposed {
+            return self.tsolve(rhs, ldim, nrhs, conjugate);
+        }
+
+        if !conjugate {
+            return self.solve(rhs, ldim, nrhs);
+        }
+
+        // A^H X = B is equivalent to (A^T)conj(X) = conj(B)
+        for val in rhs.iter_mut() {
+            *val = val.conj()
+        }
+        let res = self.solve(rhs, ldim, nrhs);
+        for val in rhs.iter_mut() {
+            *val = val.conj()
+        }
+        res
+    }
+
+    fn solve(&self, rhs: &mut [D], ldim: usize, nrhs: usize) -> Result<(), KluError> {
+        let res = unsafe {
+            D::klu_solve::<I>(
+                self.spec().symbolic_ptr(),
+                self.klu_numeric.as_ptr(),
+                I::from_usize(ldim),
+                I::from_usize(nrhs),
+                rhs.as_mut_ptr(),
+                self.settings().as_ffi(),
+            )
+        };
+
+        self.settings().check_result(res)
+    }
+
+    fn tsolve(
+        &self,
+        rhs: &mut [D],
+        ldim: usize,
+        nrhs: usize,
+        conjugate: bool,
+    ) -> Result<(), KluError> {
+        let res = unsafe {
+            D::klu_tsolve::<I>(
+                self.spec().symbolic_ptr(),
+                self.klu_numeric.as_ptr(),
+                I::from_usize(ldim),
+                I::from_usize(nrhs),
+                rhs.as_mut_ptr(),
+                conjugate,
+                self.settings().as_ffi(),
+            )
+        };
+
+        self.settings().check_result(res)
+    }
+
+    /// Computes an accurate estimate of the 1-norm condition number of the matrix using
+    /// `klu_condest`.
+    pub fn condest(&self) -> Result<f64, KluError> {
+        let res = unsafe {
+            D::klu_condest::<I>(
+                self.spec().column_offsets.as_ptr(),
+                self.matrix.data_ptr(),
+                self.spec().symbolic_ptr(),
+                self.klu_numeric.as_ptr(),
+                self.settings().as_ffi(),
+            )
+        };
+
+        self.settings().check_result(res)?;
+        Ok(self.settings().get_condest())
+    }
+
+    /// Computes the reciprocal pivot growth `min(max|A_ij|/max|U_ij|)` of the factorization
+    /// using `klu_rgrowth`. Values close to one indicate a stable factorization while small
+    /// values indicate that the factorization is numerically untrustworthy. The pivot growth
+    /// limited by [`RefactorPolicy::max_pivot_growth`](crate::RefactorPolicy::max_pivot_growth) is
+    /// the inverse of this value.
+    pub fn reciprocal_pivot_growth(&self) -> Result<f64, KluError> {
+        let res = unsafe {
+            D::klu_rgrowth::<I>(
+                self.spec().column_offsets.as_ptr(),
+                self.spec().row_indices.as_ptr(),
+                self.matrix.data_ptr(),
+                self.spec().symbolic_ptr(),
+                self.klu_numeric.as_ptr(),
+                self.settings().as_ffi(),
+            )
+        };
+
+        self.settings().check_result(res)?;
+        Ok(self.settings().get_rgrowth())
+    }
+
+    /// Computes the number of floating point operations performed by the last full
+    /// factorization using `klu_flops`.
+    pub fn flops(&self) -> Result<f64, KluError> {
+        let res = unsafe {
+            D::klu_flops::<I>(
+                self.spec().symbolic_ptr(),
+                self.klu_numeric.as_ptr(),
+                self.settings().as_ffi(),
+            )
+        };
+
+        self.settings().check_result(res)?;
+        Ok(self.settings().get_flops())
+    }
+}
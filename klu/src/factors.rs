@@ -0,0 +1,147 @@
+use crate::{Factorization, KluData, KluError, KluIndex, KluSpecRef};
+
+/// A square sparse matrix in compressed column form
+#[derive(Debug, Clone, PartialEq)]
+pub struct CscMatrix<I, D> {
+    pub dim: usize,
+    /// `column_offsets[j]..column_offsets[j+1]` are the entries of column `j`
+    pub column_offsets: Vec<I>,
+    pub row_indices: Vec<I>,
+    pub values: Vec<D>,
+}
+
+/// The factors of a KLU factorization as returned by [`Factorization::extract_factors`].
+///
+/// With `R = diag(row_scaling)`, `P` the row permutation and `Q` the column permutation
+/// the factors satisfy `(R\A)(P,Q) = L*U + F`. `L` is unit lower triangular (the unit diagonal
+/// is stored explicitly), `U` is upper triangular and `F` contains the entries above the diagonal
+/// blocks of the block triangular form.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KluFactors<I, D> {
+    pub l: CscMatrix<I, D>,
+    pub u: CscMatrix<I, D>,
+    pub f: CscMatrix<I, D>,
+    /// Row `k` of the factorized matrix is row `row_permutation[k]` of `A`
+    pub row_permutation: Vec<I>,
+    /// Column `k` of the factorized matrix is column `column_permutation[k]` of `A`
+    pub column_permutation: Vec<I>,
+    /// Row `i` of `A` was divided by `row_scaling[i]`. All ones if scaling is disabled.
+    pub row_scaling: Vec<f64>,
+    /// Block `b` of the block triangular form spans rows/columns
+    /// `block_boundaries[b]..block_boundaries[b+1]`
+    pub block_boundaries: Vec<I>,
+}
+
+/// Statistics of a KLU factorization as returned by [`Factorization::factor_stats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KluFactorStats {
+    /// The number of diagonal blocks of the block triangular form
+    pub nblocks: usize,
+    /// The number of entries in `L` (including the unit diagonal)
+    pub lnz: usize,
+    /// The number of entries in `U` (including the diagonal)
+    pub unz: usize,
+    /// The largest number of entries in `L` of a single block
+    pub max_lnz_block: usize,
+    /// The largest number of entries in `U` of a single block
+    pub max_unz_block: usize,
+    /// The number of entries in the off-diagonal blocks of the block triangular form
+    pub nzoff: usize,
+    /// The number of off-diagonal pivots chosen by the last full factorization
+    /// (refactorizations reuse the pivots)
+    pub noffdiag: usize,
+}
+
+impl<I: KluIndex, D: KluData, S: KluSpecRef<I>> Factorization<'_, I, D, S> {
+    /// Returns statistics about the structure of the current factorization.
+    pub fn factor_stats(&self) -> KluFactorStats {
+        let klu_numeric = self.klu_numeric();
+        let numeric = unsafe { I::numeric_fields(klu_numeric.as_ptr()) };
+        KluFactorStats {
+            nblocks: numeric.nblocks.into_usize(),
+            lnz: numeric.lnz.into_usize(),
+            unz: numeric.unz.into_usize(),
+            max_lnz_block: numeric.max_lnz_block.into_usize(),
+            max_unz_block: numeric.max_unz_block.into_usize(),
+            nzoff: numeric.nzoff.into_usize(),
+            noffdiag: I::get_noffdiag(unsafe { self.settings().data.as_ref() }).into_usize(),
+        }
+    }
+
+    /// Extracts the `L`, `U` and `F` factors together with the permutations, row scaling
+    /// factors and block boundaries of the current factorization using `klu_extract`.
+    pub fn extract_factors(&self) -> Result<KluFactors<I, D>, KluError> {
+        let klu_numeric = self.klu_numeric();
+        let dim = self.spec().dim();
+        let numeric = unsafe { I::numeric_fields(klu_numeric.as_ptr()) };
+        let lnz = numeric.lnz.into_usize();
+        let unz = numeric.unz.into_usize();
+        let fnz = numeric.nzoff.into_usize();
+
+        let mut l = CscMatrix::with_capacity(dim, lnz);
+        let mut u = CscMatrix::with_capacity(dim, unz);
+        let mut f = CscMatrix::with_capacity(dim, fnz);
+        let mut row_permutation = vec![I::from_usize(0); dim];
+        let mut column_permutation = vec![I::from_usize(0); dim];
+        let mut row_scaling = vec![1f64; dim];
+        let mut block_boundaries = vec![I::from_usize(0); dim + 1];
+
+        let res = unsafe {
+            D::klu_extract::<I>(
+                klu_numeric.as_ptr(),
+                self.spec().symbolic_ptr(),
+                l.column_offsets.as_mut_ptr(),
+                l.row_indices.as_mut_ptr(),
+                &mut l.values,
+                u.column_offsets.as_mut_ptr(),
+                u.row_indices.as_mut_ptr(),
+                &mut u.values,
+                f.column_offsets.as_mut_ptr(),
+                f.row_indices.as_mut_ptr(),
+                &mut f.values,
+                row_permutation.as_mut_ptr(),
+                column_permutation.as_mut_ptr(),
+                row_scaling.as_mut_ptr(),
+                block_boundaries.as_mut_ptr(),
+                self.settings().as_ffi(),
+            )
+        };
+
+        self.settings().check_result(res)?;
+
+        f.truncate();
+        let nblocks = block_boundaries
+            .iter()
+            .position(|&boundary| boundary.into_usize() == dim)
+            .unwrap_or(dim);
+        block_boundaries.truncate(nblocks + 1);
+
+        Ok(KluFactors {
+            l,
+            u,
+            f,
+            row_permutation,
+            column_permutation,
+            row_scaling,
+            block_boundaries,
+        })
+    }
+}
+
+impl<I: KluIndex, D: KluData> CscMatrix<I, D> {
+    fn with_capacity(dim: usize, nnz: usize) -> Self {
+        Self {
+            dim,
+            column_offsets: vec![I::from_usize(0); dim + 1],
+            row_indices: vec![I::from_usize(0); nnz],
+            values: vec![D::default(); nnz],
+        }
+    }
+
+    /// Drops the unused entries at the end of the buffers
+    fn truncate(&mut self) {
+        let nnz = self.column_offsets[self.dim].into_usize();
+        self.row_indices.truncate(nnz);
+        self.values.truncate(nnz);
+    }
+}
@@ -0,0 +1,111 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, BufRead};
+
+pub use matrix_market::{read_matrix_market_vector, write_matrix_market_vector};
+
+use crate::KluError;
+
+mod matrix_market;
+mod rutherford_boeing;
+
+/// The sizes stated in a file header are not trusted, so at most this many elements are
+/// preallocated for them. Larger vectors grow while their elements are read.
+const MAX_PREALLOCATED: usize = 1 << 16;
+
+/// Creates a vector for `cnt` elements announced by a file header
+fn with_header_capacity<T>(cnt: usize) -> Vec<T> {
+    Vec::with_capacity(cnt.min(MAX_PREALLOCATED))
+}
+
+/// An error that occurred while reading a matrix from a file
+#[derive(Debug)]
+pub enum MatrixReadError {
+    Io(io::Error),
+    /// The file is malformed. `line` is the (one based) line where the error was detected.
+    Parse {
+        line: usize,
+        message: String,
+    },
+    /// The file is valid but contains a matrix that is not supported (like a rectangular matrix)
+    Unsupported(String),
+    /// KLU failed to analyze the sparsity pattern
+    Klu(KluError),
+}
+
+impl Display for MatrixReadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MatrixReadError::Io(err) => write!(f, "failed to read matrix: {err}"),
+            MatrixReadError::Parse { line, message } => {
+                write!(f, "failed to parse matrix (line {line}): {message}")
+            }
+            MatrixReadError::Unsupported(message) => write!(f, "unsupported matrix: {message}"),
+            MatrixReadError::Klu(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl Error for MatrixReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MatrixReadError::Io(err) => Some(err),
+            MatrixReadError::Klu(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MatrixReadError {
+    fn from(err: io::Error) -> Self {
+        MatrixReadError::Io(err)
+    }
+}
+
+impl From<KluError> for MatrixReadError {
+    fn from(err: KluError) -> Self {
+        MatrixReadError::Klu(err)
+    }
+}
+
+/// Reads a file line by line while keeping track of the line number for error messages
+struct LineReader<R> {
+    reader: R,
+    line: usize,
+    buf: String,
+}
+
+impl<R: BufRead> LineReader<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: 0,
+            buf: String::new(),
+        }
+    }
+
+    /// Returns the next line (without the line terminator) or `None` at the end of the file
+    fn next_line(&mut self) -> Result<Option<&str>, MatrixReadError> {
+        self.buf.clear();
+        if self.reader.read_line(&mut self.buf)? == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        Ok(Some(self.buf.trim_end_matches(['\n', '\r'])))
+    }
+
+    /// Like [`next_line`](Self::next_line) but reaching the end of the file is an error
+    fn expect_line(&mut self) -> Result<&str, MatrixReadError> {
+        if self.next_line()?.is_none() {
+            return Err(self.error("unexpected end of file"));
+        }
+        Ok(self.buf.trim_end_matches(['\n', '\r']))
+    }
+
+    fn error(&self, message: impl Into<String>) -> MatrixReadError {
+        MatrixReadError::Parse {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+}
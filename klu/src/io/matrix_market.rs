@@ -0,0 +1,327 @@
+use std::io::{self, BufRead, Write};
+use std::str::{FromStr, SplitWhitespace};
+
+use super::{with_header_capacity, LineReader, MatrixReadError};
+use crate::{
+    FixedKluMatrix, KluData, KluError, KluIndex, KluMatrixBuilder, KluMatrixSpec, KluSettings,
+    KluSpecRef, PatternError,
+};
+
+/// The `field` of a Matrix Market file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Real,
+    Integer,
+    Complex,
+    Pattern,
+}
+
+struct Header {
+    array: bool,
+    field: Field,
+}
+
+impl<R: BufRead> LineReader<R> {
+    /// Returns the next line that is neither empty nor a comment
+    fn next_data_line(&mut self) -> Result<&str, MatrixReadError> {
+        loop {
+            let line = self.expect_line()?.trim();
+            if !line.is_empty() && !line.starts_with('%') {
+                break;
+            }
+        }
+        Ok(self.buf.trim())
+    }
+}
+
+fn read_header<R: BufRead>(lines: &mut LineReader<R>) -> Result<Header, MatrixReadError> {
+    let header = lines.expect_line()?.to_ascii_lowercase();
+    let tokens: Vec<_> = header.split_whitespace().collect();
+    let [banner, object, format, field, symmetry] = tokens[..] else {
+        return Err(lines.error("invalid Matrix Market header"));
+    };
+    if banner != "%%matrixmarket" {
+        return Err(lines.error("missing %%MatrixMarket banner"));
+    }
+    if object != "matrix" {
+        return Err(MatrixReadError::Unsupported(format!(
+            "Matrix Market object '{object}'"
+        )));
+    }
+    let array = match format {
+        "coordinate" => false,
+        "array" => true,
+        _ => return Err(lines.error(format!("unknown format '{format}'"))),
+    };
+    let field = match field {
+        "real" => Field::Real,
+        "integer" => Field::Integer,
+        "complex" => Field::Complex,
+        "pattern" => Field::Pattern,
+        _ => return Err(lines.error(format!("unknown field '{field}'"))),
+    };
+    if symmetry != "general" {
+        return Err(MatrixReadError::Unsupported(format!(
+            "{symmetry} matrices (only general matrices are supported)"
+        )));
+    }
+    Ok(Header { array, field })
+}
+
+/// Reads the size line and returns the number of rows, columns and entries (which is absent
+/// for arrays)
+fn read_size<R: BufRead>(
+    lines: &mut LineReader<R>,
+    array: bool,
+) -> Result<(usize, usize, Option<usize>), MatrixReadError> {
+    let size = parse_size(lines.next_data_line()?, array);
+    size.map_err(|message| lines.error(message))
+}
+
+fn parse_size(line: &str, array: bool) -> Result<(usize, usize, Option<usize>), String> {
+    let mut tokens = line.split_whitespace();
+    let rows = parse_token(tokens.next(), "number of rows")?;
+    let columns = parse_token(tokens.next(), "number of columns")?;
+    let entries = if array {
+        None
+    } else {
+        Some(parse_token(tokens.next(), "number of entries")?)
+    };
+    Ok((rows, columns, entries))
+}
+
+fn parse_token<T: FromStr>(token: Option<&str>, what: &str) -> Result<T, String> {
+    let token = token.ok_or_else(|| format!("missing {what}"))?;
+    token
+        .parse()
+        .map_err(|_| format!("invalid {what} '{token}'"))
+}
+
+/// Parses a one based index and converts it to a zero based index
+fn parse_index(token: Option<&str>, dim: usize, what: &str) -> Result<usize, String> {
+    let index: usize = parse_token(token, what)?;
+    if index == 0 || index > dim {
+        return Err(format!("{what} {index} is out of bounds"));
+    }
+    Ok(index - 1)
+}
+
+fn parse_value<D: KluData>(tokens: &mut SplitWhitespace<'_>, field: Field) -> Result<D, String> {
+    match field {
+        Field::Pattern => Ok(D::default()),
+        Field::Real | Field::Integer => {
+            Ok(D::from_parts(parse_token(tokens.next(), "value")?, 0.0))
+        }
+        Field::Complex => {
+            let re = parse_token(tokens.next(), "real part")?;
+            let im = parse_token(tokens.next(), "imaginary part")?;
+            Ok(D::from_parts(re, im))
+        }
+    }
+}
+
+fn parse_entry<D: KluData>(
+    line: &str,
+    dim: usize,
+    field: Field,
+) -> Result<(usize, usize, D), String> {
+    let mut tokens = line.split_whitespace();
+    let row = parse_index(tokens.next(), dim, "row")?;
+    let column = parse_index(tokens.next(), dim, "column")?;
+    let val = parse_value(&mut tokens, field)?;
+    Ok((row, column, val))
+}
+
+fn check_field<D: KluData>(field: Field) -> Result<(), MatrixReadError> {
+    if field == Field::Complex && !D::IS_COMPLEX {
+        return Err(MatrixReadError::Unsupported(
+            "complex values can not be read into a real matrix".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+fn write_value<D: KluData>(writer: &mut impl Write, val: D) -> io::Result<()> {
+    if D::IS_COMPLEX {
+        writeln!(writer, "{} {}", val.re(), val.im())
+    } else {
+        writeln!(writer, "{}", val.re())
+    }
+}
+
+fn field_name<D: KluData>() -> &'static str {
+    if D::IS_COMPLEX {
+        "complex"
+    } else {
+        "real"
+    }
+}
+
+impl<I: KluIndex, D: KluData> FixedKluMatrix<I, D> {
+    /// Reads a square matrix in the Matrix Market coordinate format with `general` symmetry.
+    /// `real`, `integer` and `complex` values are supported (complex values require a complex
+    /// matrix). The entries of `pattern` files are set to zero. Duplicate entries are summed.
+    ///
+    /// The spec of the matrix is analyzed using `klu_settings` and can be obtained with
+    /// [`spec`](FixedKluMatrix::spec).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MatrixReadError::Klu`] with [`PatternError::TooLarge`] if the dimension stated in
+    /// the header can not be indexed with `I` and [`MatrixReadError::Unsupported`] if the file
+    /// contains fewer entries than columns (such a matrix is structurally singular).
+    pub fn read_matrix_market(
+        reader: impl BufRead,
+        klu_settings: KluSettings<I>,
+    ) -> Result<Self, MatrixReadError> {
+        let mut lines = LineReader::new(reader);
+        let header = read_header(&mut lines)?;
+        if header.array {
+            return Err(MatrixReadError::Unsupported(
+                "dense (array) Matrix Market files".to_owned(),
+            ));
+        }
+        check_field::<D>(header.field)?;
+
+        let (rows, columns, entry_cnt) = read_size(&mut lines, false)?;
+        if rows != columns {
+            return Err(MatrixReadError::Unsupported(format!(
+                "rectangular matrix ({rows}x{columns})"
+            )));
+        }
+        let entry_cnt = entry_cnt.unwrap_or(0);
+        if rows >= I::MAX {
+            let err = PatternError::TooLarge {
+                dim: rows,
+                entries: entry_cnt,
+            };
+            return Err(KluError::from(err).into());
+        }
+
+        let mut entries = with_header_capacity(entry_cnt);
+        for _ in 0..entry_cnt {
+            let entry = parse_entry::<D>(lines.next_data_line()?, rows, header.field);
+            let (row, column, val) = entry.map_err(|message| lines.error(message))?;
+            entries.push((I::from_usize(column), I::from_usize(row), val));
+        }
+        // the pattern allocates memory for every column, so the dimension from the header is only
+        // trusted once enough entries were read to make the matrix structurally nonsingular
+        if entries.len() < rows {
+            return Err(MatrixReadError::Unsupported(format!(
+                "structurally singular matrix ({rows} columns but only {} entries)",
+                entries.len()
+            )));
+        }
+
+        let mut builder = KluMatrixBuilder::new(I::from_usize(rows));
+        for &(column, row, _) in &entries {
+            builder.add_entry(column, row);
+        }
+
+        let matrix = builder
+            .finish(klu_settings)?
+            .create_matrix()
+            .ok_or_else(|| MatrixReadError::Unsupported("matrix without entries".to_owned()))?;
+        for (column, row, val) in entries {
+            let entry = &matrix[(column, row)];
+            entry.set(entry.get() + val);
+        }
+        Ok(matrix)
+    }
+}
+
+impl<I: KluIndex, D: KluData, S: KluSpecRef<I>> FixedKluMatrix<I, D, S> {
+    /// Writes the pattern and the current values of the matrix in the Matrix Market coordinate
+    /// format. The output is not buffered so `writer` should usually be a
+    /// [`BufWriter`](std::io::BufWriter).
+    pub fn write_matrix_market(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(
+            writer,
+            "%%MatrixMarket matrix coordinate {} general",
+            field_name::<D>()
+        )?;
+        let dim = self.spec.dim();
+        writeln!(writer, "{dim} {dim} {}", self.spec.entry_cnt())?;
+        for ((column, row), val) in self.spec.entries().zip(self.data()) {
+            write!(
+                writer,
+                "{} {} ",
+                row.into_usize() + 1,
+                column.into_usize() + 1
+            )?;
+            write_value(&mut writer, val.get())?;
+        }
+        Ok(())
+    }
+}
+
+impl<I: KluIndex> KluMatrixSpec<I> {
+    /// Writes the sparsity pattern in the Matrix Market coordinate format (with the `pattern`
+    /// field). The output is not buffered so `writer` should usually be a
+    /// [`BufWriter`](std::io::BufWriter).
+    pub fn write_matrix_market(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "%%MatrixMarket matrix coordinate pattern general")?;
+        let dim = self.dim();
+        writeln!(writer, "{dim} {dim} {}", self.entry_cnt())?;
+        for (column, row) in self.entries() {
+            writeln!(
+                writer,
+                "{} {}",
+                row.into_usize() + 1,
+                column.into_usize() + 1
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads a vector (like a right hand side) stored as a dense Matrix Market array with a single
+/// column.
+pub fn read_matrix_market_vector<D: KluData>(
+    reader: impl BufRead,
+) -> Result<Vec<D>, MatrixReadError> {
+    let mut lines = LineReader::new(reader);
+    let header = read_header(&mut lines)?;
+    if !header.array || header.field == Field::Pattern {
+        return Err(MatrixReadError::Unsupported(
+            "vectors must be stored as Matrix Market arrays".to_owned(),
+        ));
+    }
+    check_field::<D>(header.field)?;
+
+    let (rows, columns, _) = read_size(&mut lines, true)?;
+    if columns != 1 {
+        return Err(MatrixReadError::Unsupported(format!(
+            "array with {columns} columns (expected a vector)"
+        )));
+    }
+
+    let mut values = with_header_capacity(rows);
+    for _ in 0..rows {
+        let val = parse_value(
+            &mut lines.next_data_line()?.split_whitespace(),
+            header.field,
+        );
+        values.push(val.map_err(|message| lines.error(message))?);
+    }
+    Ok(values)
+}
+
+/// Writes a vector (like a right hand side) as a dense Matrix Market array with a single column.
+/// The output is not buffered so `writer` should usually be a
+/// [`BufWriter`](std::io::BufWriter).
+pub fn write_matrix_market_vector<D: KluData>(
+    values: &[D],
+    mut writer: impl Write,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "%%MatrixMarket matrix array {} general",
+        field_name::<D>()
+    )?;
+    writeln!(writer, "{} 1", values.len())?;
+    for &val in values {
+        write_value(&mut writer, val)?;
+    }
+    Ok(())
+}
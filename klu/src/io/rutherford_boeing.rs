@@ -0,0 +1,426 @@
+use std::cell::Cell;
+use std::io::{self, BufRead, Write};
+
+use super::{with_header_capacity, LineReader, MatrixReadError};
+use crate::{
+    check_size, is_sorted, FixedKluMatrix, KluData, KluError, KluIndex, KluMatrixSpec, KluSettings,
+    KluSpecRef,
+};
+
+/// The width of a line in a Rutherford-Boeing file
+const LINE_WIDTH: usize = 80;
+/// The field width used for values, which are written with 17 significant digits
+const VALUE_WIDTH: usize = 26;
+
+/// The repeat count and field width of a Fortran format like `(10I8)` or `(1P,4E20.12)`
+#[derive(Debug, Clone, Copy)]
+struct FortranFormat {
+    per_line: usize,
+    width: usize,
+}
+
+impl FortranFormat {
+    fn parse(format: &str) -> Option<Self> {
+        let format = format.trim().trim_start_matches('(').trim_end_matches(')');
+        // only the last item is relevant, a leading scale factor (`1P`) does not change the layout
+        let item = format.rsplit(',').next()?.trim().to_ascii_uppercase();
+        let item = match item.find('P') {
+            Some(pos) => &item[pos + 1..],
+            None => &item[..],
+        };
+        let kind = item.find(|c: char| c.is_ascii_alphabetic())?;
+        let per_line = if kind == 0 {
+            1
+        } else {
+            item[..kind].parse().ok()?
+        };
+        let width = &item[kind + 1..];
+        let width = width[..width.find('.').unwrap_or(width.len())]
+            .parse()
+            .ok()?;
+        (per_line != 0 && width != 0).then_some(Self { per_line, width })
+    }
+
+    /// A format that fits as many fields of `width` as possible into a line
+    fn for_width(width: usize) -> Self {
+        Self {
+            per_line: LINE_WIDTH / width,
+            width,
+        }
+    }
+
+    /// The number of lines required to store `cnt` fields
+    fn lines(self, cnt: usize) -> usize {
+        cnt.div_ceil(self.per_line)
+    }
+}
+
+/// Reads `cnt` fixed width fields stored in `format`
+fn read_fields<R: BufRead, T>(
+    lines: &mut LineReader<R>,
+    format: FortranFormat,
+    cnt: usize,
+    mut parse: impl FnMut(&str) -> Option<T>,
+) -> Result<Vec<T>, MatrixReadError> {
+    let mut res = with_header_capacity(cnt);
+    while res.len() < cnt {
+        let remaining = cnt - res.len();
+        let parsed = parse_fields(
+            lines.expect_line()?,
+            format,
+            remaining,
+            &mut parse,
+            &mut res,
+        );
+        parsed.map_err(|message| lines.error(message))?;
+    }
+    Ok(res)
+}
+
+fn parse_fields<T>(
+    line: &str,
+    format: FortranFormat,
+    cnt: usize,
+    parse: &mut impl FnMut(&str) -> Option<T>,
+    dst: &mut Vec<T>,
+) -> Result<(), String> {
+    for i in 0..format.per_line.min(cnt) {
+        let end = ((i + 1) * format.width).min(line.len());
+        let field = line.get(i * format.width..end).unwrap_or("").trim();
+        if field.is_empty() {
+            return Err("missing field".to_owned());
+        }
+        dst.push(parse(field).ok_or_else(|| format!("invalid field '{field}'"))?);
+    }
+    Ok(())
+}
+
+fn parse_index<I: KluIndex>(field: &str) -> Option<I> {
+    // indices are one based
+    let index = field.parse::<usize>().ok()?.checked_sub(1)?;
+    (index <= I::MAX).then(|| I::from_usize(index))
+}
+
+fn parse_real(field: &str) -> Option<f64> {
+    // Fortran allows `D` as exponent character
+    field.replace(['D', 'd'], "E").parse().ok()
+}
+
+/// Reads a fixed width header field
+fn header_field(line: &str, start: usize, width: usize) -> &str {
+    let end = (start + width).min(line.len());
+    line.get(start.min(end)..end).unwrap_or("").trim()
+}
+
+fn parse_header_int(line: &str, start: usize, what: &str) -> Result<usize, String> {
+    let field = header_field(line, start, 14);
+    field
+        .parse()
+        .map_err(|_| format!("invalid {what} '{field}'"))
+}
+
+struct Header {
+    /// The value type (`r`, `c`, `p` or `i`)
+    field: char,
+    dim: usize,
+    entry_cnt: usize,
+    pointer_format: FortranFormat,
+    index_format: FortranFormat,
+    value_format: Option<FortranFormat>,
+}
+
+fn read_header<R: BufRead>(lines: &mut LineReader<R>) -> Result<Header, MatrixReadError> {
+    // title and key
+    lines.expect_line()?;
+    // the number of lines of each section, which is implied by the formats
+    lines.expect_line()?;
+
+    let line = lines.expect_line()?;
+    let matrix_type = header_field(line, 0, 3).to_ascii_lowercase();
+    let size = (|| {
+        let rows = parse_header_int(line, 14, "number of rows")?;
+        let columns = parse_header_int(line, 28, "number of columns")?;
+        let entry_cnt = parse_header_int(line, 42, "number of entries")?;
+        Ok((rows, columns, entry_cnt))
+    })();
+    let (rows, columns, entry_cnt) = size.map_err(|message: String| lines.error(message))?;
+
+    let mut chars = matrix_type.chars();
+    let (Some(field), Some(symmetry), Some(assembled)) = (chars.next(), chars.next(), chars.next())
+    else {
+        return Err(lines.error(format!("invalid matrix type '{matrix_type}'")));
+    };
+    if !matches!(field, 'r' | 'c' | 'p' | 'i') {
+        return Err(lines.error(format!("invalid matrix type '{matrix_type}'")));
+    }
+    if symmetry != 'u' && symmetry != 'r' || assembled != 'a' {
+        return Err(MatrixReadError::Unsupported(format!(
+            "matrix type '{matrix_type}' (only assembled unsymmetric matrices are supported)"
+        )));
+    }
+    if rows != columns {
+        return Err(MatrixReadError::Unsupported(format!(
+            "rectangular matrix ({rows}x{columns})"
+        )));
+    }
+
+    let line = lines.expect_line()?;
+    let pointer_format = FortranFormat::parse(header_field(line, 0, 16));
+    let index_format = FortranFormat::parse(header_field(line, 16, 16));
+    let value_format = FortranFormat::parse(header_field(line, 32, 20));
+    let (Some(pointer_format), Some(index_format)) = (pointer_format, index_format) else {
+        return Err(lines.error("invalid pointer or index format"));
+    };
+    if field != 'p' && value_format.is_none() {
+        return Err(lines.error("invalid value format"));
+    }
+
+    Ok(Header {
+        field,
+        dim: rows,
+        entry_cnt,
+        pointer_format,
+        index_format,
+        value_format,
+    })
+}
+
+impl<I: KluIndex, D: KluData> FixedKluMatrix<I, D> {
+    /// Reads a square matrix in the Rutherford-Boeing format. Assembled unsymmetric matrices with
+    /// real (`rua`), integer (`iua`), complex (`cua`, requires a complex matrix) or no values
+    /// (`pua`, the entries are set to zero) are supported.
+    ///
+    /// The compressed columns of the file are used directly as the pattern of the spec (see
+    /// [`KluMatrixSpec::from_csc`]) which is analyzed using `klu_settings`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MatrixReadError::Klu`] with [`PatternError::TooLarge`](crate::PatternError)
+    /// if the dimension or the number of entries stated in the header can not be indexed with `I`.
+    pub fn read_rutherford_boeing(
+        reader: impl BufRead,
+        klu_settings: KluSettings<I>,
+    ) -> Result<Self, MatrixReadError> {
+        let mut lines = LineReader::new(reader);
+        let header = read_header(&mut lines)?;
+        check_size::<I>(header.dim, header.entry_cnt).map_err(KluError::from)?;
+        if header.field == 'c' && !D::IS_COMPLEX {
+            return Err(MatrixReadError::Unsupported(
+                "complex values can not be read into a real matrix".to_owned(),
+            ));
+        }
+
+        let column_offsets = read_fields(
+            &mut lines,
+            header.pointer_format,
+            header.dim + 1,
+            parse_index::<I>,
+        )?;
+        let mut row_indices = read_fields(
+            &mut lines,
+            header.index_format,
+            header.entry_cnt,
+            parse_index::<I>,
+        )?;
+        let mut values = match header.value_format {
+            Some(format) if header.field == 'c' => {
+                let parts = read_fields(&mut lines, format, 2 * header.entry_cnt, parse_real)?;
+                parts
+                    .chunks_exact(2)
+                    .map(|parts| D::from_parts(parts[0], parts[1]))
+                    .collect()
+            }
+            Some(format) if header.field != 'p' => {
+                let values = read_fields(&mut lines, format, header.entry_cnt, parse_real)?;
+                values
+                    .into_iter()
+                    .map(|re| D::from_parts(re, 0.0))
+                    .collect()
+            }
+            _ => vec![D::default(); row_indices.len()],
+        };
+
+        // the spec sorts the rows of each column so the values have to be sorted the same way
+        let valid_offsets = column_offsets
+            .windows(2)
+            .all(|offsets| offsets[0] <= offsets[1] && offsets[1].into_usize() <= header.entry_cnt);
+        if valid_offsets {
+            for offsets in column_offsets.windows(2) {
+                let column = offsets[0].into_usize()..offsets[1].into_usize();
+                if !is_sorted(&row_indices[column.clone()]) {
+                    let mut entries: Vec<_> = row_indices[column.clone()]
+                        .iter()
+                        .copied()
+                        .zip(values[column.clone()].iter().copied())
+                        .collect();
+                    entries.sort_unstable_by_key(|&(row, _)| row);
+                    for (i, (row, val)) in column.zip(entries) {
+                        row_indices[i] = row;
+                        values[i] = val;
+                    }
+                }
+            }
+        }
+
+        let spec = KluMatrixSpec::from_csc(header.dim, column_offsets, row_indices, klu_settings)?;
+        let matrix = spec
+            .create_matrix()
+            .ok_or_else(|| MatrixReadError::Unsupported("matrix without entries".to_owned()))?;
+        for (entry, val) in matrix.data().iter().zip(values) {
+            entry.set(val);
+        }
+        Ok(matrix)
+    }
+}
+
+impl<I: KluIndex, D: KluData, S: KluSpecRef<I>> FixedKluMatrix<I, D, S> {
+    /// Writes the pattern and the current values of the matrix in the Rutherford-Boeing format
+    /// (as `rua` or `cua` matrix). `title` and `key` are truncated to 72 and 8 characters.
+    /// The output is not buffered so `writer` should usually be a
+    /// [`BufWriter`](std::io::BufWriter).
+    pub fn write_rutherford_boeing(
+        &self,
+        writer: impl Write,
+        title: &str,
+        key: &str,
+    ) -> io::Result<()> {
+        write_rutherford_boeing(&self.spec, writer, title, key, Some(self.data()))
+    }
+}
+
+impl<I: KluIndex> KluMatrixSpec<I> {
+    /// Writes the sparsity pattern in the Rutherford-Boeing format (as `pua` matrix).
+    /// `title` and `key` are truncated to 72 and 8 characters.
+    /// The output is not buffered so `writer` should usually be a
+    /// [`BufWriter`](std::io::BufWriter).
+    pub fn write_rutherford_boeing(
+        &self,
+        writer: impl Write,
+        title: &str,
+        key: &str,
+    ) -> io::Result<()> {
+        write_rutherford_boeing::<I, f64>(self, writer, title, key, None)
+    }
+}
+
+/// Writes `spec` in the Rutherford-Boeing format, without `values` only the pattern is written
+fn write_rutherford_boeing<I: KluIndex, D: KluData>(
+    spec: &KluMatrixSpec<I>,
+    mut writer: impl Write,
+    title: &str,
+    key: &str,
+    values: Option<&[Cell<D>]>,
+) -> io::Result<()> {
+    let dim = spec.dim();
+    let entry_cnt = spec.entry_cnt();
+
+    // the spec might store the transpose (CSR) so the columns are assembled from the entries
+    let mut column_offsets = vec![0; dim + 1];
+    for (column, _) in spec.entries() {
+        column_offsets[column.into_usize() + 1] += 1;
+    }
+    for i in 0..dim {
+        column_offsets[i + 1] += column_offsets[i];
+    }
+    let mut next = column_offsets.clone();
+    let mut row_indices = vec![0; entry_cnt];
+    let mut order = vec![0; entry_cnt];
+    for (i, (column, row)) in spec.entries().enumerate() {
+        let dst = &mut next[column.into_usize()];
+        row_indices[*dst] = row.into_usize();
+        order[*dst] = i;
+        *dst += 1;
+    }
+
+    let (matrix_type, value_cnt) = match (values, D::IS_COMPLEX) {
+        (None, _) => ("pua", 0),
+        (Some(_), false) => ("rua", entry_cnt),
+        (Some(_), true) => ("cua", 2 * entry_cnt),
+    };
+
+    let index_width = (entry_cnt + 1).to_string().len() + 1;
+    // pointers and indices share the same format
+    let index_format = FortranFormat::for_width(index_width);
+    let value_format = FortranFormat::for_width(VALUE_WIDTH);
+    let pointer_lines = index_format.lines(dim + 1);
+    let index_lines = index_format.lines(entry_cnt);
+    let value_lines = value_format.lines(value_cnt);
+
+    let title: String = title.chars().take(72).collect();
+    let key: String = key.chars().take(8).collect();
+    writeln!(writer, "{title:<72}{key:<8}")?;
+    writeln!(
+        writer,
+        "{:>14}{pointer_lines:>14}{index_lines:>14}{value_lines:>14}",
+        pointer_lines + index_lines + value_lines
+    )?;
+    writeln!(
+        writer,
+        "{matrix_type:<14}{dim:>14}{dim:>14}{entry_cnt:>14}{:>14}",
+        0
+    )?;
+    let index_fmt = format!("({}I{index_width})", index_format.per_line);
+    let value_fmt = match values {
+        Some(_) => format!("({}E{VALUE_WIDTH}.16E3)", value_format.per_line),
+        None => String::new(),
+    };
+    writeln!(writer, "{index_fmt:<16}{index_fmt:<16}{value_fmt:<20}")?;
+
+    write_fields(
+        &mut writer,
+        index_format,
+        column_offsets.iter().map(|i| i + 1),
+    )?;
+    write_fields(&mut writer, index_format, row_indices.iter().map(|i| i + 1))?;
+    if let Some(values) = values {
+        let values = order.iter().flat_map(|&i| {
+            let val = values[i].get();
+            let parts = if D::IS_COMPLEX {
+                [Some(val.re()), Some(val.im())]
+            } else {
+                [Some(val.re()), None]
+            };
+            parts.into_iter().flatten().map(format_real)
+        });
+        write_fields(&mut writer, value_format, values)?;
+    }
+    Ok(())
+}
+
+/// Formats a value with 17 significant digits and a three digit exponent (like `E26.16E3`)
+fn format_real(val: f64) -> String {
+    let formatted = format!("{val:.16E}");
+    match formatted.split_once('E') {
+        Some((mantissa, exponent)) => {
+            let exponent: i32 = exponent.parse().unwrap();
+            format!("{mantissa}E{exponent:+04}")
+        }
+        // NaN and infinity
+        None => formatted,
+    }
+}
+
+fn write_fields<T: ToString>(
+    writer: &mut impl Write,
+    format: FortranFormat,
+    fields: impl IntoIterator<Item = T>,
+) -> io::Result<()> {
+    let mut column = 0;
+    for field in fields {
+        write!(
+            writer,
+            "{:>width$}",
+            field.to_string(),
+            width = format.width
+        )?;
+        column += 1;
+        if column == format.per_line {
+            writeln!(writer)?;
+            column = 0;
+        }
+    }
+    if column != 0 {
+        writeln!(writer)?;
+    }
+    Ok(())
+}
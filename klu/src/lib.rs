@@ -3,18 +3,79 @@ use std::cell::Cell;
 use std::marker::PhantomData;
 use std::mem;
 use std::ops::Index;
-use std::ptr::NonNull;
+use std::ptr::{self, NonNull};
 use std::rc::Rc;
+use std::sync::atomic::{self, AtomicU64};
+use std::sync::{Arc, Mutex};
 
+use klu_sys::KluSymbolicT;
+
+pub use error::{KluError, PatternError, SettingError};
+pub use factorization::{Factorization, RefinementStats};
+pub use factors::{CscMatrix, KluFactorStats, KluFactors};
+pub use io::{read_matrix_market_vector, write_matrix_market_vector, MatrixReadError};
+pub use ordering::FillReducingOrdering;
+use ordering::{is_permutation, UserOrdering};
+use raw::KluOptions;
 pub use raw::{KluData, KluIndex};
+pub use refactor::{FactorOutcome, FullFactorReason, RefactorPolicy};
+pub use symbolic::SymbolicOrdering;
+pub use sync::{KluSpecRef, SendKluMatrix};
+pub use triplet::TripletAssembler;
 
+mod error;
+mod factorization;
+mod factors;
+mod io;
+mod ops;
+mod ordering;
 mod raw;
+mod refactor;
+#[cfg(feature = "serde")]
+mod serialize;
+mod symbolic;
+mod sync;
 #[cfg(test)]
 mod test;
+mod triplet;
+
+/// The fill reducing ordering applied to each block of the BTF form by `klu_analyze`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KluOrdering {
+    /// Approximate minimum degree ordering of `A+A^T` (the KLU default)
+    Amd,
+    /// Column approximate minimum degree ordering of `A`
+    Colamd,
+    /// The ordering installed with [`KluSettings::with_user_ordering`]
+    User,
+    /// Any other KLU ordering code (for example `2` which the vendored KLU treats as the natural
+    /// ordering). The analysis fails with [`KluError::Invalid`] if KLU does not support the
+    /// ordering.
+    Other(i64),
+}
+
+/// The row scaling applied by KLU before factorizing a matrix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KluScaling {
+    /// No scaling and the input matrix is not checked for errors
+    NoneUnchecked,
+    /// No scaling
+    None,
+    /// Each row is scaled by the sum of the absolute values in that row
+    Sum,
+    /// Each row is scaled by the largest absolute value in that row (the KLU default)
+    Max,
+    /// Any other (positive) KLU scaling code. KLU rejects these with [`KluError::Invalid`]
+    /// during factorization.
+    Other(i64),
+}
 
 #[derive(Debug)]
 pub struct KluSettings<I: KluIndex> {
     data: NonNull<I::KluCommon>,
+    user_ordering: Option<Arc<Mutex<UserOrdering<I>>>>,
 }
 
 impl<I: KluIndex> KluSettings<I> {
@@ -24,6 +85,7 @@ impl<I: KluIndex> KluSettings<I> {
             I::klu_defaults(raw);
             Self {
                 data: NonNull::new_unchecked(raw),
+                user_ordering: None,
             }
         }
     }
@@ -32,10 +94,22 @@ impl<I: KluIndex> KluSettings<I> {
         self.data.as_ptr()
     }
 
-    pub fn check_status(&self) {
+    pub fn check_status(&self) -> Result<(), KluError> {
         I::check_status(unsafe { self.data.as_ref() })
     }
 
+    /// Checks the status after a KLU call that returned `success`. KLU should always set an error
+    /// status when a call fails, a failed call with an OK status is reported as
+    /// [`KluError::CallFailed`] nonetheless.
+    pub(crate) fn check_result(&self, success: bool) -> Result<(), KluError> {
+        self.check_status()?;
+        if success {
+            Ok(())
+        } else {
+            Err(KluError::CallFailed)
+        }
+    }
+
     pub fn is_singular(&self) -> bool {
         I::is_singular(unsafe { self.data.as_ref() })
     }
@@ -43,8 +117,363 @@ impl<I: KluIndex> KluSettings<I> {
     pub fn get_rcond(&self) -> f64 {
         I::get_rcond(unsafe { self.data.as_ref() })
     }
+
+    pub fn get_condest(&self) -> f64 {
+        I::get_condest(unsafe { self.data.as_ref() })
+    }
+
+    pub fn get_rgrowth(&self) -> f64 {
+        I::get_rgrowth(unsafe { self.data.as_ref() })
+    }
+
+    pub fn get_flops(&self) -> f64 {
+        I::get_flops(unsafe { self.data.as_ref() })
+    }
+
+    fn options(&self) -> KluOptions {
+        I::get_options(unsafe { self.data.as_ref() })
+    }
+
+    fn update_options(mut self, f: impl FnOnce(&mut KluOptions)) -> Self {
+        self.set_options(f);
+        self
+    }
+
+    fn set_options(&mut self, f: impl FnOnce(&mut KluOptions)) {
+        let mut options = self.options();
+        f(&mut options);
+        I::set_options(unsafe { self.data.as_mut() }, &options);
+    }
+
+    /// The partial pivoting tolerance. A diagonal entry is preferred as pivot if its magnitude is
+    /// at least `tol` times the largest entry in the column.
+    pub fn tol(&self) -> f64 {
+        self.options().tol
+    }
+
+    /// Sets the partial pivoting tolerance (see [`tol`](Self::tol)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tol` is not within `(0, 1]`.
+    /// See [`try_with_tol`](Self::try_with_tol) for a fallible version.
+    pub fn with_tol(self, tol: f64) -> Self {
+        self.try_with_tol(tol).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Sets the partial pivoting tolerance (see [`tol`](Self::tol)).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SettingError`] if `tol` is not within `(0, 1]`
+    pub fn try_with_tol(self, tol: f64) -> Result<Self, SettingError> {
+        if !(tol > 0.0 && tol <= 1.0) {
+            return Err(SettingError {
+                setting: "tol",
+                value: tol,
+                expected: "within (0, 1]",
+            });
+        }
+        Ok(self.update_options(|options| options.tol = tol))
+    }
+
+    /// The factor by which the memory for the LU factors grows when KLU needs to reallocate.
+    pub fn memgrow(&self) -> f64 {
+        self.options().memgrow
+    }
+
+    /// Sets the growth factor for reallocations of the LU factors (see [`memgrow`](Self::memgrow)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `memgrow` is smaller than 1.
+    /// See [`try_with_memgrow`](Self::try_with_memgrow) for a fallible version.
+    pub fn with_memgrow(self, memgrow: f64) -> Self {
+        self.try_with_memgrow(memgrow)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Sets the growth factor for reallocations of the LU factors (see [`memgrow`](Self::memgrow)).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SettingError`] if `memgrow` is smaller than 1
+    pub fn try_with_memgrow(self, memgrow: f64) -> Result<Self, SettingError> {
+        if memgrow.is_nan() || memgrow < 1.0 {
+            return Err(SettingError {
+                setting: "memgrow",
+                value: memgrow,
+                expected: "at least 1",
+            });
+        }
+        Ok(self.update_options(|options| options.memgrow = memgrow))
+    }
+
+    /// The initial memory for the LU factors relative to `nnz(L)` estimated by AMD.
+    pub fn initmem_amd(&self) -> f64 {
+        self.options().initmem_amd
+    }
+
+    /// Sets the initial memory for the LU factors when AMD is used (see
+    /// [`initmem_amd`](Self::initmem_amd)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `initmem_amd` is smaller than 1.
+    /// See [`try_with_initmem_amd`](Self::try_with_initmem_amd) for a fallible version.
+    pub fn with_initmem_amd(self, initmem_amd: f64) -> Self {
+        self.try_with_initmem_amd(initmem_amd)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Sets the initial memory for the LU factors when AMD is used (see
+    /// [`initmem_amd`](Self::initmem_amd)).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SettingError`] if `initmem_amd` is smaller than 1
+    pub fn try_with_initmem_amd(self, initmem_amd: f64) -> Result<Self, SettingError> {
+        if initmem_amd.is_nan() || initmem_amd < 1.0 {
+            return Err(SettingError {
+                setting: "initmem_amd",
+                value: initmem_amd,
+                expected: "at least 1",
+            });
+        }
+        Ok(self.update_options(|options| options.initmem_amd = initmem_amd))
+    }
+
+    /// The initial memory for the LU factors relative to `nnz(A)` if AMD is not used.
+    pub fn initmem(&self) -> f64 {
+        self.options().initmem
+    }
+
+    /// Sets the initial memory for the LU factors when AMD is not used (see
+    /// [`initmem`](Self::initmem)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `initmem` is smaller than 1.
+    /// See [`try_with_initmem`](Self::try_with_initmem) for a fallible version.
+    pub fn with_initmem(self, initmem: f64) -> Self {
+        self.try_with_initmem(initmem)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Sets the initial memory for the LU factors when AMD is not used (see
+    /// [`initmem`](Self::initmem)).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SettingError`] if `initmem` is smaller than 1
+    pub fn try_with_initmem(self, initmem: f64) -> Result<Self, SettingError> {
+        if initmem.is_nan() || initmem < 1.0 {
+            return Err(SettingError {
+                setting: "initmem",
+                value: initmem,
+                expected: "at least 1",
+            });
+        }
+        Ok(self.update_options(|options| options.initmem = initmem))
+    }
+
+    /// The maximum amount of work performed by the BTF ordering. A value `<= 0` means no limit.
+    pub fn maxwork(&self) -> f64 {
+        self.options().maxwork
+    }
+
+    /// Sets the work limit for the BTF ordering (see [`maxwork`](Self::maxwork)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `maxwork` is NaN.
+    /// See [`try_with_maxwork`](Self::try_with_maxwork) for a fallible version.
+    pub fn with_maxwork(self, maxwork: f64) -> Self {
+        self.try_with_maxwork(maxwork)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Sets the work limit for the BTF ordering (see [`maxwork`](Self::maxwork)).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SettingError`] if `maxwork` is NaN
+    pub fn try_with_maxwork(self, maxwork: f64) -> Result<Self, SettingError> {
+        if maxwork.is_nan() {
+            return Err(SettingError {
+                setting: "maxwork",
+                value: maxwork,
+                expected: "a number",
+            });
+        }
+        Ok(self.update_options(|options| options.maxwork = maxwork))
+    }
+
+    /// Whether the matrix is permuted to block triangular form before it is factorized.
+    pub fn btf(&self) -> bool {
+        self.options().btf
+    }
+
+    /// Enables or disables the BTF preordering (see [`btf`](Self::btf)).
+    pub fn with_btf(self, btf: bool) -> Self {
+        self.update_options(|options| options.btf = btf)
+    }
+
+    /// The fill reducing ordering used by the symbolic analysis.
+    pub fn ordering(&self) -> KluOrdering {
+        match self.options().ordering {
+            0 => KluOrdering::Amd,
+            1 => KluOrdering::Colamd,
+            3 => KluOrdering::User,
+            ordering => KluOrdering::Other(ordering),
+        }
+    }
+
+    /// Sets the fill reducing ordering used by the symbolic analysis.
+    /// [`KluOrdering::User`] requires that an ordering was installed with
+    /// [`with_user_ordering`](Self::with_user_ordering), otherwise the analysis fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the code of [`KluOrdering::Other`] is negative or does not fit into `I`.
+    /// See [`try_with_ordering`](Self::try_with_ordering) for a fallible version.
+    pub fn with_ordering(self, ordering: KluOrdering) -> Self {
+        self.try_with_ordering(ordering)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Sets the fill reducing ordering used by the symbolic analysis (see
+    /// [`with_ordering`](Self::with_ordering)).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SettingError`] if the code of [`KluOrdering::Other`] is negative or does not
+    /// fit into `I`
+    pub fn try_with_ordering(self, ordering: KluOrdering) -> Result<Self, SettingError> {
+        let ordering = match ordering {
+            KluOrdering::Amd => 0,
+            KluOrdering::Colamd => 1,
+            KluOrdering::User => 3,
+            KluOrdering::Other(ordering) => check_code::<I>("ordering", ordering)?,
+        };
+        Ok(self.update_options(|options| options.ordering = ordering))
+    }
+
+    /// Installs a user supplied fill reducing ordering and selects [`KluOrdering::User`].
+    ///
+    /// The ordering is called by KLU for every diagonal block of the matrix larger than 3x3.
+    /// If it fails (or produces an invalid permutation) the analysis fails with
+    /// [`KluError::Invalid`]. Panics inside the ordering are propagated to the caller once KLU
+    /// returns.
+    pub fn with_user_ordering(mut self, ordering: impl FillReducingOrdering<I>) -> Self {
+        let user_ordering = UserOrdering::new(Box::new(ordering));
+        I::set_user_order(
+            unsafe { self.data.as_mut() },
+            Some(UserOrdering::<I>::trampoline),
+            UserOrdering::as_user_data(&user_ordering),
+        );
+        // the ordering is behind an `Arc` so the user_data pointer stays valid when it is moved here
+        self.user_ordering = Some(user_ordering);
+        self.with_ordering(KluOrdering::User)
+    }
+
+    /// Creates a copy of these settings (including the KLU status) that shares the user ordering
+    /// so that it can be used to analyze another pattern.
+    fn copy_for_analysis(&self) -> Self {
+        unsafe {
+            let raw = std::alloc::alloc(Layout::new::<I::KluCommon>()) as *mut I::KluCommon;
+            ptr::copy_nonoverlapping(self.as_ffi(), raw, 1);
+            Self {
+                data: NonNull::new_unchecked(raw),
+                user_ordering: self.user_ordering.clone(),
+            }
+        }
+    }
+
+    /// Creates a copy of these settings for a matrix so that the KLU status of different
+    /// matrices created from the same spec is kept separate. The user ordering is not copied as
+    /// it is only used during analysis.
+    fn copy_for_matrix(&self) -> Self {
+        let mut res = self.copy_for_analysis();
+        I::set_user_order(unsafe { res.data.as_mut() }, None, ptr::null_mut());
+        res.user_ordering = None;
+        res
+    }
+
+    fn resume_user_ordering_panic(&mut self) {
+        if let Some(user_ordering) = &self.user_ordering {
+            UserOrdering::resume_panic(user_ordering)
+        }
+    }
+
+    /// The row scaling applied before factorization.
+    pub fn scaling(&self) -> KluScaling {
+        match self.options().scale {
+            // KLU treats every negative value like -1
+            ..=-1 => KluScaling::NoneUnchecked,
+            0 => KluScaling::None,
+            1 => KluScaling::Sum,
+            2 => KluScaling::Max,
+            scale => KluScaling::Other(scale),
+        }
+    }
+
+    /// Sets the row scaling applied before factorization.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the code of [`KluScaling::Other`] is negative or does not fit into `I`.
+    /// See [`try_with_scaling`](Self::try_with_scaling) for a fallible version.
+    pub fn with_scaling(self, scaling: KluScaling) -> Self {
+        self.try_with_scaling(scaling)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Sets the row scaling applied before factorization.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SettingError`] if the code of [`KluScaling::Other`] is negative or does not
+    /// fit into `I`
+    pub fn try_with_scaling(self, scaling: KluScaling) -> Result<Self, SettingError> {
+        let scale = match scaling {
+            KluScaling::NoneUnchecked => -1,
+            KluScaling::None => 0,
+            KluScaling::Sum => 1,
+            KluScaling::Max => 2,
+            KluScaling::Other(scale) => check_code::<I>("scaling", scale)?,
+        };
+        Ok(self.update_options(|options| options.scale = scale))
+    }
+
+    /// Whether factorization stops as soon as a zero pivot is encountered.
+    /// If this is disabled singular matrices are still factorized completely.
+    pub fn halt_if_singular(&self) -> bool {
+        self.options().halt_if_singular
+    }
+
+    /// Sets whether factorization stops at the first zero pivot (see
+    /// [`halt_if_singular`](Self::halt_if_singular)).
+    pub fn with_halt_if_singular(self, halt_if_singular: bool) -> Self {
+        self.update_options(|options| options.halt_if_singular = halt_if_singular)
+    }
+}
+
+/// Checks that an `Other` ordering or scaling code is not negative and can be stored by KLU
+fn check_code<I: KluIndex>(setting: &'static str, code: i64) -> Result<i64, SettingError> {
+    if code < 0 || code as u64 > I::MAX as u64 {
+        return Err(SettingError {
+            setting,
+            value: code as f64,
+            expected: "a non-negative code that fits into the index type",
+        });
+    }
+    Ok(code)
 }
 
+// SAFETY: The settings own their `KluCommon` and the user ordering is `Send`
+unsafe impl<I: KluIndex> Send for KluSettings<I> {}
+
 impl<I: KluIndex> Drop for KluSettings<I> {
     fn drop(&mut self) {
         unsafe { std::alloc::dealloc(self.as_ffi() as *mut u8, Layout::new::<I::KluCommon>()) }
@@ -58,13 +487,20 @@ impl<I: KluIndex> Default for KluSettings<I> {
 }
 
 /// A compressed column form SparsMatrix whose shape is fixed
-pub struct FixedKluMatrix<I: KluIndex, D: KluData> {
-    spec: Rc<KluMatrixSpec<I>>,
+///
+/// The spec is shared trough `S` which is either an `Rc` (the default) or an `Arc`,
+/// see [`SendKluMatrix`].
+pub struct FixedKluMatrix<I: KluIndex, D: KluData, S: KluSpecRef<I> = Rc<KluMatrixSpec<I>>> {
+    spec: S,
+    /// A copy of the spec settings that receives the KLU status of this matrix
+    settings: KluSettings<I>,
     data: Option<NonNull<[D]>>,
     klu_numeric: Option<NonNull<I::KluNumeric>>,
+    /// The number of refactorizations since the last full factorization
+    refactor_count: usize,
 }
 
-impl<I: KluIndex, D: KluData> FixedKluMatrix<I, D> {
+impl<I: KluIndex, D: KluData, S: KluSpecRef<I>> FixedKluMatrix<I, D, S> {
     /// Obtain the allocation of the matrix data
     /// This function can be used with [`from_raw`] and [`KluMatrixSpec::reinit`] to reuse a matrix
     /// allocation.
@@ -92,7 +528,7 @@ impl<I: KluIndex, D: KluData> FixedKluMatrix<I, D> {
     ///
     /// the constructed matrix if the matrix is not empty.
     /// If the matrix is empty `None` is retruned instead
-    pub fn new_with_alloc(spec: Rc<KluMatrixSpec<I>>, mut alloc: Vec<D>) -> Option<Self> {
+    pub fn new_with_alloc(spec: S, mut alloc: Vec<D>) -> Option<Self> {
         if spec.row_indices.is_empty() {
             return None;
         }
@@ -105,10 +541,13 @@ impl<I: KluIndex, D: KluData> FixedKluMatrix<I, D> {
         // However
         let data = Box::leak(alloc.into_boxed_slice());
 
+        let settings = spec.settings.copy_for_matrix();
         Some(Self {
             spec,
+            settings,
             data: Some(data.into()),
             klu_numeric: None,
+            refactor_count: 0,
         })
     }
 
@@ -121,7 +560,7 @@ impl<I: KluIndex, D: KluData> FixedKluMatrix<I, D> {
     ///
     /// the constructed matrix if the matrix is not empty.
     /// If the matrix is empty `None` is retruned instead
-    pub fn new(spec: Rc<KluMatrixSpec<I>>) -> Option<Self> {
+    pub fn new(spec: S) -> Option<Self> {
         Self::new_with_alloc(spec, Vec::new())
     }
 
@@ -155,137 +594,190 @@ impl<I: KluIndex, D: KluData> FixedKluMatrix<I, D> {
     }
 
     /// Perform lu_factorization of the matrix using KLU
-    /// If the matrix was already factorized previously and `refactor_threshold` is given, it is
-    /// first attempted to refactorize the matrix. If this fails (either due to an KLU error or
-    /// because the rcond is larger than the provided threshold) full factorization is preformed.
-    ///
-    /// Calling to funciton is a prerequisite to calling [`solve_linear_system`]
-    pub fn lu_factorize(&mut self, refactor_threshold: Option<f64>) -> bool {
-        match (self.klu_numeric, refactor_threshold) {
-            (Some(klu_numeric), None) => unsafe {
-                D::klu_free_numeric::<I>(&mut klu_numeric.as_ptr(), self.spec.settings.as_ffi())
-            },
-            (Some(klu_numeric), Some(rcond_threshold)) => {
-                let res = unsafe {
-                    D::klu_refactor(
-                        // KLU does not modify these values they only need to be mut bceuase C has no concept of a const pointer
-                        self.spec.column_offsets.as_ptr(),
-                        self.spec.row_indices.as_ptr(),
-                        self.data_ptr(),
-                        self.spec.klu_symbolic.as_ptr(),
-                        klu_numeric.as_ptr(),
-                        self.spec.settings.as_ffi(),
-                    ) && D::klu_rcond::<I>(
-                        self.spec.klu_symbolic.as_ptr(),
-                        klu_numeric.as_ptr(),
-                        self.spec.settings.as_ffi(),
-                    )
-                };
-                self.spec.settings.check_status();
-                if !self.spec.settings.is_singular()
-                    && self.spec.settings.get_rcond() <= rcond_threshold
-                {
-                    // refactoring succeded we are done here
-                    assert!(res, "KLU produced unkown error");
-                    return false;
-                }
-
-                unsafe {
-                    D::klu_free_numeric::<I>(&mut klu_numeric.as_ptr(), self.spec.settings.as_ffi())
-                }
-                self.klu_numeric = None;
+    /// If the matrix was already factorized previously and `policy` allows it, it is first
+    /// attempted to refactorize the matrix with the previous pivots. If this fails (either due to
+    /// an KLU error or because the refactorization violates a limit of the policy) a full
+    /// factorization is performed.
+    ///
+    /// # Returns
+    ///
+    /// A [`Factorization`] that is used to solve linear systems with the matrix. It borrows the
+    /// matrix so the values can not be modified while it is alive.
+    /// [`Factorization::outcome`] reports whether the matrix was refactored or fully factorized
+    /// (and why) or [`FactorOutcome::Singular`] if the matrix is singular and
+    /// [`KluSettings::halt_if_singular`] is unset.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`KluError`] if KLU failed to factorize the matrix (including
+    /// [`KluError::Singular`] if the matrix is singular and [`KluSettings::halt_if_singular`] is
+    /// set). The matrix is left unfactorized in that case.
+    pub fn lu_factorize(
+        &mut self,
+        policy: RefactorPolicy,
+    ) -> Result<Factorization<'_, I, D, S>, KluError> {
+        let reason = match self.klu_numeric {
+            None => FullFactorReason::Initial,
+            Some(_) if !policy.refactor() => FullFactorReason::Disabled,
+            Some(_)
+                if policy
+                    .full_factor_every()
+                    .is_some_and(|n| self.refactor_count + 1 >= n) =>
+            {
+                FullFactorReason::Scheduled
             }
-
-            _ => (),
+            Some(klu_numeric) => match self.refactor(klu_numeric, &policy) {
+                Ok(()) => {
+                    self.refactor_count += 1;
+                    return Ok(Factorization::new(
+                        self,
+                        klu_numeric,
+                        FactorOutcome::Refactored,
+                    ));
+                }
+                Err(reason) => reason,
+            },
         };
 
+        if let Some(klu_numeric) = self.klu_numeric.take() {
+            unsafe { D::klu_free_numeric::<I>(&mut klu_numeric.as_ptr(), self.settings().as_ffi()) }
+        }
+        self.refactor_count = 0;
+
         let klu_numeric = unsafe {
             D::klu_factor(
                 // KLU does not modify these values they only need to be mut because C has not concept of a const pointer
                 self.spec.column_offsets.as_ptr(),
                 self.spec.row_indices.as_ptr(),
                 self.data_ptr(),
-                self.spec.klu_symbolic.as_ptr(),
-                self.spec.settings.as_ffi(),
+                self.spec.symbolic_ptr(),
+                self.settings().as_ffi(),
             )
         };
-        self.spec.settings.check_status();
-        if self.spec.settings.is_singular() {
-            return true;
-        }
-
-        let klu_numeric = NonNull::new(klu_numeric).expect("KLU retruned a valid numeric object");
-        self.klu_numeric = Some(klu_numeric);
-        false
+        // KLU still returns a numeric object for singular matrices if `halt_if_singular` is unset
+        self.klu_numeric = NonNull::new(klu_numeric);
+        let outcome = match self.settings().check_status() {
+            Ok(()) => FactorOutcome::FullFactor { reason },
+            Err(KluError::Singular { column }) if self.klu_numeric.is_some() => {
+                FactorOutcome::Singular { column }
+            }
+            Err(err) => return Err(err),
+        };
+        let klu_numeric = self.klu_numeric.ok_or(KluError::Invalid)?;
+        Ok(Factorization::new(self, klu_numeric, outcome))
     }
 
-    /// solves the linear system `Ax=b`. The `b` vector is read from `rhs` at the beginning of the
-    /// function. After the functin completes `x` was written into `rhs`
-    ///
-    /// **Note**: This function assumes that [`lu_factorize`] was called first.
-    /// If this is not the case this functions panics.
-    pub fn solve_linear_system(&self, rhs: &mut [D]) {
-        // TODO allow solving multiple rhs
-
-        let klu_numeric = self
-            .klu_numeric
-            .expect("factorize must be called before solve");
+    /// Refactors the matrix with the pivots of `klu_numeric` and checks the result against the
+    /// limits of `policy`
+    fn refactor(
+        &self,
+        klu_numeric: NonNull<I::KluNumeric>,
+        policy: &RefactorPolicy,
+    ) -> Result<(), FullFactorReason> {
         let res = unsafe {
-            D::klu_solve::<I>(
-                self.spec.klu_symbolic.as_ptr(),
+            D::klu_refactor(
+                // KLU does not modify these values they only need to be mut bceuase C has no concept of a const pointer
+                self.spec.column_offsets.as_ptr(),
+                self.spec.row_indices.as_ptr(),
+                self.data_ptr(),
+                self.spec.symbolic_ptr(),
                 klu_numeric.as_ptr(),
-                I::from_usize(rhs.len()),
-                I::from_usize(1),
-                rhs.as_mut_ptr(),
-                self.spec.settings.as_ffi(),
+                self.settings().as_ffi(),
             )
         };
+        // any failure during refactorization is handled by falling back to a full factorization
+        if !res || self.settings().check_status().is_err() {
+            return Err(FullFactorReason::RefactorFailed);
+        }
 
-        self.spec.settings.check_status();
+        if let Some(min_rcond) = policy.min_rcond() {
+            let res = unsafe {
+                D::klu_rcond::<I>(
+                    self.spec.symbolic_ptr(),
+                    klu_numeric.as_ptr(),
+                    self.settings().as_ffi(),
+                )
+            };
+            if !res || self.settings().check_status().is_err() {
+                return Err(FullFactorReason::RefactorFailed);
+            }
+            let rcond = self.settings().get_rcond();
+            // NaN indicates a failed refactorization
+            if rcond.is_nan() || rcond < min_rcond {
+                return Err(FullFactorReason::LowRcond { rcond });
+            }
+        }
 
-        assert!(res, "KLU produced unkown error");
+        if let Some(max_pivot_growth) = policy.max_pivot_growth() {
+            let res = unsafe {
+                D::klu_rgrowth::<I>(
+                    self.spec.column_offsets.as_ptr(),
+                    self.spec.row_indices.as_ptr(),
+                    self.data_ptr(),
+                    self.spec.symbolic_ptr(),
+                    klu_numeric.as_ptr(),
+                    self.settings().as_ffi(),
+                )
+            };
+            if !res || self.settings().check_status().is_err() {
+                return Err(FullFactorReason::RefactorFailed);
+            }
+            let growth = self.settings().get_rgrowth().recip();
+            if growth.is_nan() || growth > max_pivot_growth {
+                return Err(FullFactorReason::PivotGrowth { growth });
+            }
+        }
+        Ok(())
     }
 
-    /// solves the linear system `A^T x=b` The `b` vector is read from `rhs` at the beginning of the
-    /// function. After the functin completes `x` was written into `rhs`
-    ///
-    /// **Note**: This function assumes that [`lu_factorize`] was called first.
-    /// If this is not the case this functions panics.
-    pub fn solve_linear_tranose_system(&self, rhs: &mut [D]) {
-        // TODO allow solving multiple rhs
-
-        let klu_numeric = self
-            .klu_numeric
-            .expect("factorize must be called before solve");
-        let res = unsafe {
-            D::klu_tsolve::<I>(
-                self.spec.klu_symbolic.as_ptr(),
-                klu_numeric.as_ptr(),
-                I::from_usize(rhs.len()),
-                I::from_usize(1),
-                rhs.as_mut_ptr(),
-                self.spec.settings.as_ffi(),
-            )
-        };
-
-        self.spec.settings.check_status();
+    /// The spec that describes the sparsity pattern of this matrix
+    pub fn spec(&self) -> &S {
+        &self.spec
+    }
 
-        assert!(res, "KLU produced unkown error");
+    /// The settings that receive the KLU status (like [`KluSettings::is_singular`] or
+    /// [`KluSettings::get_rcond`]) of this matrix. These are a copy of the settings of the spec
+    /// so the status of other matrices created from the same spec is not affected.
+    pub fn settings(&self) -> &KluSettings<I> {
+        &self.settings
     }
+
     fn free_numeric(&self, klu_numeric: Option<NonNull<I::KluNumeric>>) {
         if let Some(klu_numeric) = klu_numeric {
-            unsafe {
-                D::klu_free_numeric::<I>(&mut klu_numeric.as_ptr(), self.spec.settings.as_ffi())
-            }
+            unsafe { D::klu_free_numeric::<I>(&mut klu_numeric.as_ptr(), self.settings().as_ffi()) }
         }
     }
     pub fn get(&self, column: I, row: I) -> Option<&Cell<D>> {
         let offset = self.spec.offset(column, row)?;
         Some(&self[offset])
     }
+
+    /// The entry identified by `handle` (see [`KluMatrixSpec::resolve`]).
+    ///
+    /// # Returns
+    ///
+    /// The entry or `None` if `handle` was not resolved by the spec of this matrix
+    pub fn get_at(&self, handle: EntryHandle<I>) -> Option<&Cell<D>> {
+        if handle.generation != self.spec.generation {
+            return None;
+        }
+        Some(&self.data()[handle.offset])
+    }
+
+    /// Adds `val` to the entry identified by `handle` without searching the pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::Invalid`] if `handle` was not resolved by the spec of this matrix (see
+    /// [`KluMatrixSpec::resolve`]).
+    pub fn add_at(&self, handle: EntryHandle<I>, val: D) -> Result<(), KluError> {
+        let entry = self.get_at(handle).ok_or(KluError::Invalid)?;
+        entry.set(entry.get() + val);
+        Ok(())
+    }
 }
 
-impl<I: KluIndex, D: KluData> Index<usize> for FixedKluMatrix<I, D> {
+impl<I: KluIndex, D: KluData, S: KluSpecRef<I>> Index<usize> for FixedKluMatrix<I, D, S> {
     type Output = Cell<D>;
 
     fn index(&self, i: usize) -> &Self::Output {
@@ -293,7 +785,7 @@ impl<I: KluIndex, D: KluData> Index<usize> for FixedKluMatrix<I, D> {
     }
 }
 
-impl<I: KluIndex, D: KluData> Index<(I, I)> for FixedKluMatrix<I, D> {
+impl<I: KluIndex, D: KluData, S: KluSpecRef<I>> Index<(I, I)> for FixedKluMatrix<I, D, S> {
     type Output = Cell<D>;
 
     fn index(&self, (column, row): (I, I)) -> &Self::Output {
@@ -301,7 +793,7 @@ impl<I: KluIndex, D: KluData> Index<(I, I)> for FixedKluMatrix<I, D> {
     }
 }
 
-impl<I: KluIndex, D: KluData> Drop for FixedKluMatrix<I, D> {
+impl<I: KluIndex, D: KluData, S: KluSpecRef<I>> Drop for FixedKluMatrix<I, D, S> {
     fn drop(&mut self) {
         let klu_numeric = self.klu_numeric.take();
         self.free_numeric(klu_numeric);
@@ -314,12 +806,40 @@ impl<I: KluIndex, D: KluData> Drop for FixedKluMatrix<I, D> {
     }
 }
 
+/// A precomputed location of a matrix entry returned by [`KluMatrixSpec::resolve`].
+///
+/// A handle is only valid for matrices created from the spec that resolved it and is invalidated
+/// when the spec is reinitialized. Using it with any other matrix is reported as an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntryHandle<I: KluIndex> {
+    offset: usize,
+    /// The generation of the spec that resolved this handle
+    generation: u64,
+    pd: PhantomData<I>,
+}
+
+impl<I: KluIndex> EntryHandle<I> {
+    /// The offset of the entry within the matrix data
+    pub fn offset(self) -> usize {
+        self.offset
+    }
+}
+
+/// Every (re)initialization of a spec receives a unique generation so that entry handles
+/// from other specs (or from before a reinitialization) are detected.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
 #[derive(Debug)]
 pub struct KluMatrixSpec<I: KluIndex> {
     column_offsets: Box<[I]>,
+    /// The rows within each column are always sorted so [`offset`](Self::offset) can use a
+    /// binary search
     row_indices: Box<[I]>,
+    /// Whether the pattern stores the transpose of the matrix (see [`from_csr`](Self::from_csr))
+    transposed: bool,
+    generation: u64,
     settings: KluSettings<I>,
-    klu_symbolic: NonNull<I::KluSymbolic>,
+    klu_symbolic: Option<NonNull<I::KluSymbolic>>,
     pd: PhantomData<I::KluSymbolic>,
 }
 
@@ -328,85 +848,408 @@ impl<I: KluIndex> KluMatrixSpec<I> {
         self.row_indices.len()
     }
 
+    /// The number of rows/columns of the (square) matrix
+    pub fn dim(&self) -> usize {
+        self.column_offsets.len().saturating_sub(1)
+    }
+
     /// Constructs a new matrix specification by reusing the allocations within this spec.
     /// See [`new`] for details
-    pub fn reinit(&mut self, columns: &[Vec<I>]) {
+    ///
+    /// If the analysis fails the spec is left without a symbolic analysis and any matrix
+    /// created from it will fail to factorize. If the pattern is invalid the spec is also left
+    /// without any columns.
+    pub fn reinit(&mut self, columns: &[Vec<I>]) -> Result<(), KluError> {
         self.free_symbolic();
-        self.init(columns)
+        self.init(columns, None)
     }
 
-    fn init(&mut self, columns: &[Vec<I>]) {
+    fn init(
+        &mut self,
+        columns: &[Vec<I>],
+        given: Option<GivenPermutations<'_, I>>,
+    ) -> Result<(), KluError> {
+        self.set_pattern(columns)?;
+        self.analyze_pattern(given)
+    }
+
+    /// Stores the pattern described by `columns` in the (reused) arrays of this spec.
+    /// If the pattern is invalid the spec is left without any columns.
+    fn set_pattern(&mut self, columns: &[Vec<I>]) -> Result<(), PatternError> {
         let mut column_offsets: Vec<_> =
             mem::replace(&mut self.column_offsets, Box::new([])).into();
-        column_offsets.clear();
+        let mut row_indices: Vec<_> = mem::replace(&mut self.row_indices, Box::new([])).into();
+        self.transposed = false;
 
-        column_offsets.reserve(columns.len() + 1);
-        column_offsets.push(I::from_usize(0));
-        let mut num_entries = 0;
-        column_offsets.extend(columns.iter().map(|colum| {
-            num_entries += colum.len();
-            I::from_usize(num_entries)
-        }));
+        let dim = columns.len();
+        let num_entries = columns.iter().map(Vec::len).sum();
+        check_size::<I>(dim, num_entries)?;
 
-        let num_cols = I::from_usize(columns.len());
-        let mut row_indices: Vec<_> = mem::replace(&mut self.row_indices, Box::new([])).into();
+        column_offsets.clear();
+        column_offsets.reserve(dim + 1);
+        column_offsets.push(I::from_usize(0));
         row_indices.clear();
         row_indices.reserve(num_entries);
 
-        for colmun in columns {
-            row_indices.extend_from_slice(colmun)
+        for (column, rows) in columns.iter().enumerate() {
+            let start = row_indices.len();
+            row_indices.extend_from_slice(rows);
+            let rows = &mut row_indices[start..];
+            // sorted columns allow finding entries with a binary search
+            if !is_sorted(rows) {
+                rows.sort_unstable();
+            }
+            check_rows(column, rows, dim)?;
+            column_offsets.push(I::from_usize(row_indices.len()));
         }
+        self.column_offsets = column_offsets.into_boxed_slice();
+        self.row_indices = row_indices.into_boxed_slice();
+        Ok(())
+    }
 
-        let klu_symbolic = unsafe {
-            I::klu_analyze(
-                num_cols,
-                column_offsets.as_mut_ptr(),
-                row_indices.as_mut_ptr(),
-                self.settings.as_ffi(),
-            )
+    /// Performs the symbolic analysis of the pattern stored in this spec
+    fn analyze_pattern(&mut self, given: Option<GivenPermutations<'_, I>>) -> Result<(), KluError> {
+        let num_cols = I::from_usize(self.dim());
+        let klu_symbolic = match given {
+            Some(given) => unsafe {
+                I::klu_analyze_given(
+                    num_cols,
+                    self.column_offsets.as_ptr(),
+                    self.row_indices.as_ptr(),
+                    given.rows.map_or(ptr::null(), |rows| rows.as_ptr()),
+                    given
+                        .columns
+                        .map_or(ptr::null(), |columns| columns.as_ptr()),
+                    self.settings.as_ffi(),
+                )
+            },
+            None => unsafe {
+                I::klu_analyze(
+                    num_cols,
+                    self.column_offsets.as_mut_ptr(),
+                    self.row_indices.as_mut_ptr(),
+                    self.settings.as_ffi(),
+                )
+            },
         };
 
-        self.settings.check_status();
-        self.klu_symbolic = NonNull::new(klu_symbolic)
-            .expect("klu_analyze returns a non null pointer if the status is ok");
-        self.column_offsets = column_offsets.into_boxed_slice();
-        self.row_indices = row_indices.into_boxed_slice();
+        self.klu_symbolic = NonNull::new(klu_symbolic);
+        self.generation = GENERATION.fetch_add(1, atomic::Ordering::Relaxed);
+        self.settings.resume_user_ordering_panic();
+        self.settings.check_status()
     }
 
     /// Constructs a new matrix spec from a column sparse matrix description.
-    pub fn new(columns: &[Vec<I>], klu_settings: KluSettings<I>) -> Rc<Self> {
-        let mut res = Self {
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::InvalidPattern`] if a row is out of bounds or present multiple times
+    /// within a column, if `columns` is empty or if the pattern can not be indexed with `I`.
+    /// Returns any other error if KLU fails to analyze the sparsity pattern.
+    pub fn new(columns: &[Vec<I>], klu_settings: KluSettings<I>) -> Result<Rc<Self>, KluError> {
+        Self::analyze(columns, klu_settings).map(Rc::new)
+    }
+
+    /// Constructs a new matrix spec of dimension `dim` directly from compressed column (CSC)
+    /// arrays. The rows of column `j` are `row_indices[column_offsets[j]..column_offsets[j+1]]`.
+    ///
+    /// The buffers are taken over by the spec and the rows of each column are sorted in place if
+    /// necessary. The entries are not copied unless a vector has spare capacity, in which case
+    /// converting it into a boxed slice reallocates it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::InvalidPattern`] if `column_offsets` does not contain `dim + 1`
+    /// monotonically increasing offsets starting at zero and ending at `row_indices.len()`, if a
+    /// row index is out of bounds or if a column contains the same row multiple times.
+    /// Returns any other error if KLU fails to analyze the sparsity pattern.
+    pub fn from_csc(
+        dim: usize,
+        column_offsets: Vec<I>,
+        row_indices: Vec<I>,
+        klu_settings: KluSettings<I>,
+    ) -> Result<Rc<Self>, KluError> {
+        Self::from_compressed(dim, column_offsets, row_indices, klu_settings, false).map(Rc::new)
+    }
+
+    /// Constructs a new matrix spec of dimension `dim` from compressed row (CSR) arrays.
+    /// The columns of row `i` are `column_indices[row_offsets[i]..row_offsets[i+1]]`.
+    ///
+    /// The arrays are taken over as the CSC pattern of `A^T` (which is only copied if a vector
+    /// has spare capacity, see [`from_csc`](Self::from_csc)). KLU therefore
+    /// factorizes `A^T` and all solves of matrices created from this spec are transparently
+    /// mapped to the corresponding transposed solve. Entries are still accessed with their
+    /// `(column, row)` position in `A`.
+    ///
+    /// **Note**: [`Factorization::condest`] and [`Factorization::extract_factors`] describe the
+    /// factorization of `A^T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`from_csc`](Self::from_csc)
+    pub fn from_csr(
+        dim: usize,
+        row_offsets: Vec<I>,
+        column_indices: Vec<I>,
+        klu_settings: KluSettings<I>,
+    ) -> Result<Rc<Self>, KluError> {
+        Self::from_compressed(dim, row_offsets, column_indices, klu_settings, true).map(Rc::new)
+    }
+
+    /// Whether the spec stores the transpose of the matrix (see [`from_csr`](Self::from_csr))
+    pub fn is_transposed(&self) -> bool {
+        self.transposed
+    }
+
+    fn from_compressed(
+        dim: usize,
+        column_offsets: Vec<I>,
+        mut row_indices: Vec<I>,
+        klu_settings: KluSettings<I>,
+        transposed: bool,
+    ) -> Result<Self, KluError> {
+        let check = |column_offsets: &[I], row_indices: &mut [I]| -> Result<(), PatternError> {
+            check_size::<I>(dim, row_indices.len())?;
+            if column_offsets.len() != dim + 1 {
+                return Err(PatternError::OffsetCount {
+                    expected: dim + 1,
+                    found: column_offsets.len(),
+                });
+            }
+            if column_offsets[0] != I::from_usize(0) {
+                return Err(PatternError::InvalidOffset { index: 0 });
+            }
+            if let Some(index) = column_offsets
+                .windows(2)
+                .position(|offsets| offsets[0] > offsets[1])
+            {
+                return Err(PatternError::InvalidOffset { index: index + 1 });
+            }
+            if column_offsets[dim] != I::from_usize(row_indices.len()) {
+                return Err(PatternError::InvalidOffset { index: dim });
+            }
+
+            for (column, offsets) in column_offsets.windows(2).enumerate() {
+                let rows = &mut row_indices[offsets[0].into_usize()..offsets[1].into_usize()];
+                if !is_sorted(rows) {
+                    rows.sort_unstable();
+                }
+                check_rows(column, rows, dim)?;
+            }
+            Ok(())
+        };
+        check(&column_offsets, &mut row_indices).map_err(|err| {
+            if transposed {
+                err.transpose()
+            } else {
+                err
+            }
+        })?;
+
+        let mut res = Self::empty(klu_settings);
+        res.transposed = transposed;
+        res.column_offsets = column_offsets.into_boxed_slice();
+        res.row_indices = row_indices.into_boxed_slice();
+        res.analyze_pattern(None)?;
+        Ok(res)
+    }
+
+    /// Constructs a new matrix spec from a column sparse matrix description that can be shared
+    /// between threads (see [`SendKluMatrix`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`new`](Self::new).
+    pub fn new_shared(
+        columns: &[Vec<I>],
+        klu_settings: KluSettings<I>,
+    ) -> Result<Arc<Self>, KluError> {
+        Self::analyze(columns, klu_settings).map(Arc::new)
+    }
+
+    /// A spec without any columns and without a symbolic analysis that can be initialized with
+    /// [`reinit`](Self::reinit)
+    fn empty(klu_settings: KluSettings<I>) -> Self {
+        Self {
             column_offsets: Box::new([]),
             row_indices: Box::new([]),
-            klu_symbolic: NonNull::dangling(),
+            transposed: false,
+            generation: GENERATION.fetch_add(1, atomic::Ordering::Relaxed),
+            klu_symbolic: None,
             settings: klu_settings,
             pd: PhantomData,
+        }
+    }
+
+    fn analyze(columns: &[Vec<I>], klu_settings: KluSettings<I>) -> Result<Self, KluError> {
+        let mut res = Self::empty(klu_settings);
+        res.init(columns, None)?;
+        Ok(res)
+    }
+
+    /// Constructs a new matrix spec from a column sparse matrix description using the provided
+    /// row and column permutations instead of computing a fill reducing ordering.
+    /// `None` is treated as the identity permutation.
+    ///
+    /// This allows reusing an ordering that was computed previously for a structurally similar
+    /// matrix. If BTF is enabled in `klu_settings` the permutations are still refined by the
+    /// block triangular form.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::Invalid`] if a permutation is not a valid permutation of the columns
+    /// and the same errors as [`new`](Self::new) otherwise.
+    pub fn with_permutations(
+        columns: &[Vec<I>],
+        row_permutation: Option<&[I]>,
+        column_permutation: Option<&[I]>,
+        klu_settings: KluSettings<I>,
+    ) -> Result<Rc<Self>, KluError> {
+        // KLU does not validate the permutations and would access out of bounds instead
+        for perm in [row_permutation, column_permutation].into_iter().flatten() {
+            if perm.len() != columns.len() || !is_permutation(perm) {
+                return Err(KluError::Invalid);
+            }
+        }
+
+        let mut res = Self::empty(klu_settings);
+        let given = GivenPermutations {
+            rows: row_permutation,
+            columns: column_permutation,
         };
-        res.init(columns);
-        Rc::new(res)
+        res.init(columns, Some(given))?;
+        Ok(Rc::new(res))
     }
 
     pub fn create_matrix<D: KluData>(self: Rc<Self>) -> Option<FixedKluMatrix<I, D>> {
         FixedKluMatrix::new(self)
     }
 
+    pub fn create_send_matrix<D: KluData>(self: Arc<Self>) -> Option<SendKluMatrix<I, D>> {
+        FixedKluMatrix::new(self)
+    }
+
+    /// Returns the offset of the entry at `column` and `row` within the matrix data or `None` if
+    /// the entry is not part of the pattern.
+    ///
+    /// The rows of each column are sorted when the spec is constructed (so the data is not
+    /// necessarily stored in the order the rows were provided) which allows a binary search.
     pub fn offset(&self, column: I, row: I) -> Option<usize> {
+        let (column, row) = if self.transposed {
+            (row, column)
+        } else {
+            (column, row)
+        };
         let column = column.into_usize();
         let end = self.column_offsets[column + 1].into_usize();
 
         let column_offset = self.column_offsets[column].into_usize();
 
-        let pos = self.row_indices[column_offset..end]
-            .iter()
-            .position(|&r| r == row)?;
+        let rows = &self.row_indices[column_offset..end];
+        let pos = rows.binary_search(&row).ok()?;
         Some(column_offset + pos)
     }
 
-    fn free_symbolic(&self) {
-        unsafe { I::klu_free_symbolic(&mut self.klu_symbolic.as_ptr(), self.settings.as_ffi()) }
+    /// The `(column, row)` position of every entry in the order they are stored in the matrix data
+    fn entries(&self) -> impl Iterator<Item = (I, I)> + '_ {
+        self.column_offsets
+            .windows(2)
+            .enumerate()
+            .flat_map(move |(column, offsets)| {
+                let column = I::from_usize(column);
+                let rows = &self.row_indices[offsets[0].into_usize()..offsets[1].into_usize()];
+                rows.iter().map(move |&row| {
+                    if self.transposed {
+                        (row, column)
+                    } else {
+                        (column, row)
+                    }
+                })
+            })
+    }
+
+    /// Resolves the location of the entry at `column` and `row` once so that it can be written
+    /// repeatedly with [`FixedKluMatrix::add_at`] without searching the pattern.
+    ///
+    /// # Returns
+    ///
+    /// The handle of the entry or `None` if the entry is not part of the pattern
+    pub fn resolve(&self, column: I, row: I) -> Option<EntryHandle<I>> {
+        let offset = self.offset(column, row)?;
+        Some(EntryHandle {
+            offset,
+            generation: self.generation,
+            pd: PhantomData,
+        })
+    }
+
+    /// The fields of the symbolic analysis or `None` if the analysis failed. KLU only modifies the
+    /// symbolic object during analysis (which requires `&mut self`) so shared access is sound.
+    fn symbolic(&self) -> Option<&KluSymbolicT<I>> {
+        let symbolic = self.klu_symbolic?;
+        Some(unsafe { I::symbolic_fields(symbolic.as_ptr()) })
+    }
+
+    /// Returns a pointer to the symbolic analysis or null if the analysis failed.
+    /// KLU reports an `INVALID` status if it encounters a null symbolic object.
+    fn symbolic_ptr(&self) -> *mut I::KluSymbolic {
+        self.klu_symbolic
+            .map_or(ptr::null_mut(), |symbolic| symbolic.as_ptr())
+    }
+
+    fn free_symbolic(&mut self) {
+        if let Some(klu_symbolic) = self.klu_symbolic.take() {
+            unsafe { I::klu_free_symbolic(&mut klu_symbolic.as_ptr(), self.settings.as_ffi()) }
+        }
     }
 }
 
+/// Checks that a pattern with `dim` columns and `entries` entries is not empty and can be
+/// indexed with `I`
+fn check_size<I: KluIndex>(dim: usize, entries: usize) -> Result<(), PatternError> {
+    if dim == 0 {
+        return Err(PatternError::Empty);
+    }
+    if dim >= I::MAX || entries >= I::MAX {
+        return Err(PatternError::TooLarge { dim, entries });
+    }
+    Ok(())
+}
+
+/// Whether `rows` is sorted (`slice::is_sorted` requires Rust 1.82)
+fn is_sorted<I: KluIndex>(rows: &[I]) -> bool {
+    rows.windows(2).all(|rows| rows[0] <= rows[1])
+}
+
+/// Checks that the sorted `rows` of `column` are unique and within a matrix of dimension `dim`
+fn check_rows<I: KluIndex>(column: usize, rows: &[I], dim: usize) -> Result<(), PatternError> {
+    let (Some(&first), Some(&last)) = (rows.first(), rows.last()) else {
+        return Ok(());
+    };
+    for row in [first, last] {
+        if row < I::from_usize(0) || row >= I::from_usize(dim) {
+            return Err(PatternError::EntryOutOfBounds {
+                column: column as i64,
+                row: row.into_i64(),
+                dim,
+            });
+        }
+    }
+    if let Some(rows) = rows.windows(2).find(|rows| rows[0] == rows[1]) {
+        return Err(PatternError::DuplicateEntry {
+            column,
+            row: rows[0].into_usize(),
+        });
+    }
+    Ok(())
+}
+
+/// The user provided permutations passed to `klu_analyze_given`
+struct GivenPermutations<'a, I> {
+    rows: Option<&'a [I]>,
+    columns: Option<&'a [I]>,
+}
+
 impl<I: KluIndex> Drop for KluMatrixSpec<I> {
     fn drop(&mut self) {
         self.free_symbolic()
@@ -455,11 +1298,34 @@ impl<I: KluIndex> KluMatrixBuilder<I> {
         &self.columns[..self.dim.into_usize()]
     }
 
-    pub fn finish(&self, klu_settings: KluSettings<I>) -> Rc<KluMatrixSpec<I>> {
+    pub fn finish(&self, klu_settings: KluSettings<I>) -> Result<Rc<KluMatrixSpec<I>>, KluError> {
         KluMatrixSpec::new(self.columns(), klu_settings)
     }
 
-    pub fn reinit(&self, spec: &mut KluMatrixSpec<I>) {
+    /// Constructs a spec that can be shared between threads, see [`KluMatrixSpec::new_shared`]
+    pub fn finish_shared(
+        &self,
+        klu_settings: KluSettings<I>,
+    ) -> Result<Arc<KluMatrixSpec<I>>, KluError> {
+        KluMatrixSpec::new_shared(self.columns(), klu_settings)
+    }
+
+    /// Constructs a spec with the given permutations, see [`KluMatrixSpec::with_permutations`]
+    pub fn finish_given(
+        &self,
+        row_permutation: Option<&[I]>,
+        column_permutation: Option<&[I]>,
+        klu_settings: KluSettings<I>,
+    ) -> Result<Rc<KluMatrixSpec<I>>, KluError> {
+        KluMatrixSpec::with_permutations(
+            self.columns(),
+            row_permutation,
+            column_permutation,
+            klu_settings,
+        )
+    }
+
+    pub fn reinit(&self, spec: &mut KluMatrixSpec<I>) -> Result<(), KluError> {
         spec.reinit(self.columns())
     }
 }
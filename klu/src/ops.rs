@@ -0,0 +1,184 @@
+use crate::{FixedKluMatrix, KluData, KluError, KluIndex, KluSpecRef};
+
+impl<I: KluIndex, D: KluData, S: KluSpecRef<I>> FixedKluMatrix<I, D, S> {
+    /// Computes `y = Ax` using the current matrix values.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::Invalid`] if the length of `x` or `y` is not the matrix dimension.
+    pub fn mul_vec(&self, x: &[D], y: &mut [D]) -> Result<(), KluError> {
+        self.check_dims(x, y)?;
+        y.fill(D::zero());
+        self.gaxpy(x, y)
+    }
+
+    /// Computes `y = A^T x` using the current matrix values.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::Invalid`] if the length of `x` or `y` is not the matrix dimension.
+    pub fn mul_vec_transposed(&self, x: &[D], y: &mut [D]) -> Result<(), KluError> {
+        self.check_dims(x, y)?;
+        y.fill(D::zero());
+        self.gaxpy_transposed(x, y)
+    }
+
+    /// Computes `y = A^H x` (where `A^H` is the conjugate transpose of `A`) using the current
+    /// matrix values. For real matrices this is equivalent to
+    /// [`mul_vec_transposed`](Self::mul_vec_transposed).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::Invalid`] if the length of `x` or `y` is not the matrix dimension.
+    pub fn mul_vec_conjugate_transposed(&self, x: &[D], y: &mut [D]) -> Result<(), KluError> {
+        self.check_dims(x, y)?;
+        y.fill(D::zero());
+        self.gaxpy_conjugate_transposed(x, y)
+    }
+
+    /// Computes `y += Ax` using the current matrix values. Starting with `y = b` and a negated
+    /// `x` yields the residual `b - Ax`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::Invalid`] if the length of `x` or `y` is not the matrix dimension.
+    pub fn gaxpy(&self, x: &[D], y: &mut [D]) -> Result<(), KluError> {
+        self.accumulate(x, y, false, false)
+    }
+
+    /// Computes `y += A^T x` using the current matrix values.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::Invalid`] if the length of `x` or `y` is not the matrix dimension.
+    pub fn gaxpy_transposed(&self, x: &[D], y: &mut [D]) -> Result<(), KluError> {
+        self.accumulate(x, y, true, false)
+    }
+
+    /// Computes `y += A^H x` using the current matrix values.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::Invalid`] if the length of `x` or `y` is not the matrix dimension.
+    pub fn gaxpy_conjugate_transposed(&self, x: &[D], y: &mut [D]) -> Result<(), KluError> {
+        self.accumulate(x, y, true, true)
+    }
+
+    /// The 1-norm (largest absolute column sum) of the current matrix values
+    pub fn norm_one(&self) -> f64 {
+        self.max_abs_sum(!self.spec.transposed)
+    }
+
+    /// The infinity-norm (largest absolute row sum) of the current matrix values
+    pub fn norm_inf(&self) -> f64 {
+        self.max_abs_sum(self.spec.transposed)
+    }
+
+    /// Computes `y += |A||x|` which is used to scale the residual when computing the
+    /// componentwise backward error
+    pub(crate) fn abs_gaxpy(&self, x: &[D], y: &mut [f64]) {
+        let data = self.data();
+        for (column, offsets) in self.spec.column_offsets.windows(2).enumerate() {
+            let entries = offsets[0].into_usize()..offsets[1].into_usize();
+            for (row, val) in self.spec.row_indices[entries.clone()]
+                .iter()
+                .zip(&data[entries])
+            {
+                let (row, column) = if self.spec.transposed {
+                    (column, row.into_usize())
+                } else {
+                    (row.into_usize(), column)
+                };
+                y[row] += val.get().abs() * x[column].abs();
+            }
+        }
+    }
+
+    /// The largest number of entries in a row of the matrix
+    pub(crate) fn max_row_entries(&self) -> usize {
+        if self.spec.transposed {
+            return self
+                .spec
+                .column_offsets
+                .windows(2)
+                .map(|offsets| offsets[1].into_usize() - offsets[0].into_usize())
+                .max()
+                .unwrap_or(0);
+        }
+        let mut entries = vec![0; self.spec.dim()];
+        for row in &self.spec.row_indices {
+            entries[row.into_usize()] += 1;
+        }
+        entries.into_iter().max().unwrap_or(0)
+    }
+
+    /// Computes `y += op(A) x` directly on the compressed pattern of the spec. If the spec stores
+    /// `A^T` (see [`KluMatrixSpec::from_csr`](crate::KluMatrixSpec::from_csr)) the stored pattern
+    /// is simply traversed the other way around.
+    fn accumulate(
+        &self,
+        x: &[D],
+        y: &mut [D],
+        transpose: bool,
+        conjugate: bool,
+    ) -> Result<(), KluError> {
+        self.check_dims(x, y)?;
+
+        let data = self.data();
+        let value = |entry: usize| {
+            let val = data[entry].get();
+            if conjugate {
+                val.conj()
+            } else {
+                val
+            }
+        };
+        let transpose = transpose != self.spec.transposed;
+        for (column, offsets) in self.spec.column_offsets.windows(2).enumerate() {
+            let entries = offsets[0].into_usize()..offsets[1].into_usize();
+            if transpose {
+                let mut sum = D::zero();
+                for entry in entries {
+                    sum += value(entry) * x[self.spec.row_indices[entry].into_usize()];
+                }
+                y[column] += sum;
+            } else {
+                for entry in entries {
+                    y[self.spec.row_indices[entry].into_usize()] += value(entry) * x[column];
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_dims(&self, x: &[D], y: &[D]) -> Result<(), KluError> {
+        let dim = self.spec.dim();
+        if x.len() != dim || y.len() != dim {
+            return Err(KluError::Invalid);
+        }
+        Ok(())
+    }
+
+    /// The largest absolute sum of the stored columns (`per_column`) or stored rows
+    fn max_abs_sum(&self, per_column: bool) -> f64 {
+        let data = self.data();
+        if per_column {
+            self.spec
+                .column_offsets
+                .windows(2)
+                .map(|offsets| {
+                    data[offsets[0].into_usize()..offsets[1].into_usize()]
+                        .iter()
+                        .map(|val| val.get().abs())
+                        .sum()
+                })
+                .fold(0.0, f64::max)
+        } else {
+            let mut sums = vec![0f64; self.spec.dim()];
+            for (row, val) in self.spec.row_indices.iter().zip(data) {
+                sums[row.into_usize()] += val.get().abs();
+            }
+            sums.into_iter().fold(0.0, f64::max)
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::any::Any;
+use std::fmt::{self, Debug, Formatter};
+use std::os::raw::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::KluIndex;
+
+/// A user supplied fill reducing ordering that KLU applies to each diagonal block of the
+/// (optionally BTF permuted) matrix instead of AMD/COLAMD.
+///
+/// Closures with the signature of [`order`](FillReducingOrdering::order) implement this trait.
+pub trait FillReducingOrdering<I: KluIndex>: Send + 'static {
+    /// Computes a fill reducing column permutation for a square block of dimension `perm.len()`.
+    /// The block is provided in compressed column form by `column_offsets` and `row_indices`.
+    /// The permutation is written to `perm` where `perm[k] = j` means that column `j` of the block
+    /// becomes column `k`.
+    ///
+    /// # Returns
+    ///
+    /// An estimate of the number of nonzeros in the `L` factor of the block (used by KLU to size
+    /// its initial allocation) or `None` if the ordering failed. If no good estimate is available
+    /// the number of entries in the block is a reasonable choice.
+    fn order(&mut self, column_offsets: &[I], row_indices: &[I], perm: &mut [I]) -> Option<usize>;
+}
+
+impl<I, F> FillReducingOrdering<I> for F
+where
+    I: KluIndex,
+    F: FnMut(&[I], &[I], &mut [I]) -> Option<usize> + Send + 'static,
+{
+    fn order(&mut self, column_offsets: &[I], row_indices: &[I], perm: &mut [I]) -> Option<usize> {
+        self(column_offsets, row_indices, perm)
+    }
+}
+
+/// The ordering installed in a [`KluSettings`](crate::KluSettings). KLU receives a pointer to
+/// the mutex around this struct as `user_data` so it must not move while it is installed (it is
+/// always stored in an `Arc`). The ordering is shared by copies of the settings that are used to
+/// analyze another pattern, the mutex serializes concurrent analyses.
+pub(crate) struct UserOrdering<I: KluIndex> {
+    ordering: Box<dyn FillReducingOrdering<I>>,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+impl<I: KluIndex> Debug for UserOrdering<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserOrdering").finish_non_exhaustive()
+    }
+}
+
+impl<I: KluIndex> UserOrdering<I> {
+    pub(crate) fn new(ordering: Box<dyn FillReducingOrdering<I>>) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self {
+            ordering,
+            panic: None,
+        }))
+    }
+
+    /// Resumes a panic that occurred inside the ordering while it was called by KLU.
+    /// Panics can not unwind across the FFI boundary so they are caught in the trampoline and
+    /// stored until KLU returns.
+    pub(crate) fn resume_panic(this: &Mutex<Self>) {
+        // panics are caught while the lock is held so the mutex is never poisoned
+        let payload = this
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .panic
+            .take();
+        if let Some(payload) = payload {
+            panic::resume_unwind(payload)
+        }
+    }
+
+    /// The function that is installed as `user_order` in the `KluCommon` struct.
+    ///
+    /// # Safety
+    ///
+    /// `user_data` of `common` must point to a live `Mutex<UserOrdering<I>>`
+    pub(crate) unsafe extern "C" fn trampoline(
+        n: I,
+        column_offsets: *mut I,
+        row_indices: *mut I,
+        perm: *mut I,
+        common: *mut I::KluCommon,
+    ) -> I {
+        let this = &*(I::user_data(&*common) as *const Mutex<Self>);
+        let mut this = this.lock().unwrap_or_else(PoisonError::into_inner);
+        let n = n.into_usize();
+        let column_offsets = slice::from_raw_parts(column_offsets as *const I, n + 1);
+        let row_indices =
+            slice::from_raw_parts(row_indices as *const I, column_offsets[n].into_usize());
+        let perm = slice::from_raw_parts_mut(perm, n);
+
+        let ordering = &mut this.ordering;
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            ordering.order(column_offsets, row_indices, perm)
+        }));
+
+        match res {
+            // KLU does not validate the permutation so an invalid one would cause out of bounds accesses
+            // the estimate is only used to size allocations so it is clamped to the index type
+            Ok(Some(lnz)) if is_permutation(perm) => I::from_usize(lnz.clamp(1, I::MAX)),
+            Ok(_) => I::from_usize(0),
+            Err(payload) => {
+                this.panic = Some(payload);
+                I::from_usize(0)
+            }
+        }
+    }
+
+    pub(crate) fn as_user_data(this: &Arc<Mutex<Self>>) -> *mut c_void {
+        Arc::as_ptr(this) as *mut c_void
+    }
+}
+
+pub(crate) fn is_permutation<I: KluIndex>(perm: &[I]) -> bool {
+    let mut seen = vec![false; perm.len()];
+    perm.iter().all(|&col| {
+        col >= I::from_usize(0)
+            && col < I::from_usize(perm.len())
+            && !std::mem::replace(&mut seen[col.into_usize()], true)
+    })
+}
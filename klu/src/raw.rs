@@ -1,16 +1,22 @@
 use klu_sys::{
-    klu_analyze, klu_defaults, klu_factor, klu_free_numeric, klu_free_symbolic, klu_l_analyze,
-    klu_l_defaults, klu_l_factor, klu_l_free_numeric, klu_l_free_symbolic, klu_l_rcond,
-    klu_l_refactor, klu_l_solve, klu_l_tsolve, klu_rcond, klu_refactor, klu_solve, klu_tsolve,
-    klu_z_factor, klu_z_free_numeric, klu_z_rcond, klu_z_refactor, klu_z_solve, klu_z_tsolve,
-    klu_zl_factor, klu_zl_free_numeric, klu_zl_rcond, klu_zl_refactor, klu_zl_solve, klu_zl_tsolve,
-    KluCommon, KluLCommon, KluLNumeric, KluLSymbolic, KluNumeric, KluSymbolic,
+    klu_analyze, klu_analyze_given, klu_condest, klu_defaults, klu_extract, klu_factor, klu_flops,
+    klu_free_numeric, klu_free_symbolic, klu_l_analyze, klu_l_analyze_given, klu_l_condest,
+    klu_l_defaults, klu_l_extract, klu_l_factor, klu_l_flops, klu_l_free_numeric,
+    klu_l_free_symbolic, klu_l_rcond, klu_l_refactor, klu_l_rgrowth, klu_l_solve, klu_l_tsolve,
+    klu_rcond, klu_refactor, klu_rgrowth, klu_solve, klu_tsolve, klu_z_condest, klu_z_extract,
+    klu_z_factor, klu_z_flops, klu_z_free_numeric, klu_z_rcond, klu_z_refactor, klu_z_rgrowth,
+    klu_z_solve, klu_z_tsolve, klu_zl_condest, klu_zl_extract, klu_zl_factor, klu_zl_flops,
+    klu_zl_free_numeric, klu_zl_rcond, klu_zl_refactor, klu_zl_rgrowth, klu_zl_solve,
+    klu_zl_tsolve, KluCommon, KluLCommon, KluLNumeric, KluLSymbolic, KluNumeric, KluNumericT,
+    KluSymbolic, KluSymbolicT,
 };
 use num_complex::{Complex64, ComplexFloat};
 
 use crate::raw::sealed::Sealed;
+use crate::KluError;
 use std::fmt::Debug;
 use std::ops::{Add, AddAssign, Div, Mul, Sub};
+use std::os::raw::c_void;
 
 mod sealed {
     use num_complex::Complex64;
@@ -39,6 +45,13 @@ pub trait KluData:
     + ComplexFloat<Real = f64>
     + 'static
 {
+    /// Whether values of this type have an imaginary part
+    const IS_COMPLEX: bool;
+
+    /// Constructs a value from its real and imaginary part.
+    /// The imaginary part is ignored for real types.
+    fn from_parts(re: f64, im: f64) -> Self;
+
     unsafe fn klu_solve<I: KluIndex>(
         symbolic: *mut I::KluSymbolic,
         numeric: *mut I::KluNumeric,
@@ -48,12 +61,15 @@ pub trait KluData:
         common: *mut I::KluCommon,
     ) -> bool;
 
+    /// Solves `A^T x = b` or `A^H x = b` if `conjugate` is set.
+    /// For real values both are equivalent.
     unsafe fn klu_tsolve<I: KluIndex>(
         symbolic: *mut I::KluSymbolic,
         numeric: *mut I::KluNumeric,
         rhs_dimension: I,
         number_rhs: I,
         rhs_data: *mut Self,
+        conjugate: bool,
         common: *mut I::KluCommon,
     ) -> bool;
 
@@ -84,20 +100,98 @@ pub trait KluData:
         numeric: *mut I::KluNumeric,
         common: *mut I::KluCommon,
     ) -> bool;
+
+    unsafe fn klu_condest<I: KluIndex>(
+        colum_offsets: *const I,
+        data: *mut Self,
+        symbolic: *mut I::KluSymbolic,
+        numeric: *mut I::KluNumeric,
+        common: *mut I::KluCommon,
+    ) -> bool;
+
+    unsafe fn klu_rgrowth<I: KluIndex>(
+        colum_offsets: *const I,
+        row_indices: *const I,
+        data: *mut Self,
+        symbolic: *mut I::KluSymbolic,
+        numeric: *mut I::KluNumeric,
+        common: *mut I::KluCommon,
+    ) -> bool;
+
+    unsafe fn klu_flops<I: KluIndex>(
+        symbolic: *mut I::KluSymbolic,
+        numeric: *mut I::KluNumeric,
+        common: *mut I::KluCommon,
+    ) -> bool;
+
+    /// Extracts the factorization into the provided buffers (see `klu_extract`).
+    /// The slices must be large enough to hold the respective part of the factorization.
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn klu_extract<I: KluIndex>(
+        numeric: *mut I::KluNumeric,
+        symbolic: *mut I::KluSymbolic,
+        l_column_offsets: *mut I,
+        l_row_indices: *mut I,
+        l_data: &mut [Self],
+        u_column_offsets: *mut I,
+        u_row_indices: *mut I,
+        u_data: &mut [Self],
+        f_column_offsets: *mut I,
+        f_row_indices: *mut I,
+        f_data: &mut [Self],
+        row_permutation: *mut I,
+        column_permutation: *mut I,
+        row_scaling: *mut f64,
+        block_boundaries: *mut I,
+        common: *mut I::KluCommon,
+    ) -> bool;
+}
+
+/// The user configurable fields of `KluCommon`/`KluLCommon` in an index independent representation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KluOptions {
+    pub tol: f64,
+    pub memgrow: f64,
+    pub initmem_amd: f64,
+    pub initmem: f64,
+    pub maxwork: f64,
+    pub btf: bool,
+    pub ordering: i64,
+    pub scale: i64,
+    pub halt_if_singular: bool,
 }
 
+/// The signature of the `user_order` callback in `KluCommon`/`KluLCommon`
+pub type KluUserOrder<I> =
+    unsafe extern "C" fn(I, *mut I, *mut I, *mut I, *mut <I as KluIndex>::KluCommon) -> I;
+
 /// Values that can be used by the KLU solver.
 /// The functions of this trait are all unsafe because they directly call the underlying C implementation.
 #[allow(clippy::missing_safety_doc)]
 pub trait KluIndex:
-    PartialEq + PartialOrd + Ord + Debug + Copy + Clone + Eq + Sealed + Add<Output = Self>
+    PartialEq + PartialOrd + Ord + Debug + Copy + Clone + Eq + Sealed + Add<Output = Self> + 'static
 {
     type KluCommon: Debug;
     type KluNumeric: Debug;
     type KluSymbolic: Debug;
 
+    /// The largest value that can be represented by this index type
+    const MAX: usize;
+
     fn from_usize(val: usize) -> Self;
     fn into_usize(self) -> usize;
+    fn into_i64(self) -> i64;
+
+    /// Provides read access to the fields of a symbolic object
+    unsafe fn symbolic_fields<'a>(symbolic: *const Self::KluSymbolic) -> &'a KluSymbolicT<Self>;
+
+    /// Provides mutable access to the fields of a symbolic object
+    unsafe fn symbolic_fields_mut<'a>(
+        symbolic: *mut Self::KluSymbolic,
+    ) -> &'a mut KluSymbolicT<Self>;
+
+    /// Provides access to the fields of a numeric object
+    unsafe fn numeric_fields<'a>(numeric: *mut Self::KluNumeric) -> &'a KluNumericT<Self>;
 
     unsafe fn klu_defaults(common: *mut Self::KluCommon) -> Self;
 
@@ -108,6 +202,15 @@ pub trait KluIndex:
         common: *mut Self::KluCommon,
     ) -> *mut Self::KluSymbolic;
 
+    unsafe fn klu_analyze_given(
+        dim: Self,
+        colum_offsets: *const Self,
+        row_indices: *const Self,
+        row_permutation: *const Self,
+        column_permutation: *const Self,
+        common: *mut Self::KluCommon,
+    ) -> *mut Self::KluSymbolic;
+
     unsafe fn klu_free_symbolic(
         symbolic: *mut *mut Self::KluSymbolic,
         common: *mut Self::KluCommon,
@@ -180,6 +283,7 @@ pub trait KluIndex:
         rhs_dimension: Self,
         number_rhs: Self,
         rhs_data: *mut f64,
+        conjugate: bool,
         common: *mut Self::KluCommon,
     ) -> bool;
 
@@ -187,9 +291,21 @@ pub trait KluIndex:
 
     unsafe fn klu_z_free_numeric(numeric: *mut *mut Self::KluNumeric, common: *mut Self::KluCommon);
 
-    fn check_status(common: &Self::KluCommon);
+    fn check_status(common: &Self::KluCommon) -> Result<(), KluError>;
     fn get_rcond(common: &Self::KluCommon) -> f64;
+    fn get_condest(common: &Self::KluCommon) -> f64;
+    fn get_rgrowth(common: &Self::KluCommon) -> f64;
+    fn get_flops(common: &Self::KluCommon) -> f64;
+    fn get_noffdiag(common: &Self::KluCommon) -> Self;
     fn is_singular(common: &Self::KluCommon) -> bool;
+    fn get_options(common: &Self::KluCommon) -> KluOptions;
+    fn set_options(common: &mut Self::KluCommon, options: &KluOptions);
+    fn user_data(common: &Self::KluCommon) -> *mut c_void;
+    fn set_user_order(
+        common: &mut Self::KluCommon,
+        user_order: Option<KluUserOrder<Self>>,
+        user_data: *mut c_void,
+    );
 
     unsafe fn klu_rcond(
         symbolic: *mut Self::KluSymbolic,
@@ -202,9 +318,104 @@ pub trait KluIndex:
         numeric: *mut Self::KluNumeric,
         common: *mut Self::KluCommon,
     ) -> bool;
+
+    unsafe fn klu_condest(
+        colum_offsets: *const Self,
+        data: *mut f64,
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool;
+
+    unsafe fn klu_rgrowth(
+        colum_offsets: *const Self,
+        row_indices: *const Self,
+        data: *mut f64,
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool;
+
+    unsafe fn klu_flops(
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool;
+
+    unsafe fn klu_z_condest(
+        colum_offsets: *const Self,
+        data: *mut f64,
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool;
+
+    unsafe fn klu_z_rgrowth(
+        colum_offsets: *const Self,
+        row_indices: *const Self,
+        data: *mut f64,
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool;
+
+    unsafe fn klu_z_flops(
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool;
+
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn klu_extract(
+        numeric: *mut Self::KluNumeric,
+        symbolic: *mut Self::KluSymbolic,
+        l_column_offsets: *mut Self,
+        l_row_indices: *mut Self,
+        l_data: *mut f64,
+        u_column_offsets: *mut Self,
+        u_row_indices: *mut Self,
+        u_data: *mut f64,
+        f_column_offsets: *mut Self,
+        f_row_indices: *mut Self,
+        f_data: *mut f64,
+        row_permutation: *mut Self,
+        column_permutation: *mut Self,
+        row_scaling: *mut f64,
+        block_boundaries: *mut Self,
+        common: *mut Self::KluCommon,
+    ) -> bool;
+
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn klu_z_extract(
+        numeric: *mut Self::KluNumeric,
+        symbolic: *mut Self::KluSymbolic,
+        l_column_offsets: *mut Self,
+        l_row_indices: *mut Self,
+        l_data: *mut f64,
+        l_imag: *mut f64,
+        u_column_offsets: *mut Self,
+        u_row_indices: *mut Self,
+        u_data: *mut f64,
+        u_imag: *mut f64,
+        f_column_offsets: *mut Self,
+        f_row_indices: *mut Self,
+        f_data: *mut f64,
+        f_imag: *mut f64,
+        row_permutation: *mut Self,
+        column_permutation: *mut Self,
+        row_scaling: *mut f64,
+        block_boundaries: *mut Self,
+        common: *mut Self::KluCommon,
+    ) -> bool;
 }
 
 impl KluData for f64 {
+    const IS_COMPLEX: bool = false;
+
+    fn from_parts(re: f64, _im: f64) -> Self {
+        re
+    }
+
     unsafe fn klu_solve<I: KluIndex>(
         symbolic: *mut <I as KluIndex>::KluSymbolic,
         numeric: *mut <I as KluIndex>::KluNumeric,
@@ -229,6 +440,8 @@ impl KluData for f64 {
         rhs_dimension: I,
         number_rhs: I,
         rhs_data: *mut Self,
+        // the transpose and conjugate transpose of a real matrix are identical
+        _conjugate: bool,
         common: *mut I::KluCommon,
     ) -> bool {
         I::klu_tsolve(
@@ -276,9 +489,82 @@ impl KluData for f64 {
     ) -> bool {
         I::klu_rcond(symbolic, numeric, common)
     }
+
+    unsafe fn klu_condest<I: KluIndex>(
+        colum_offsets: *const I,
+        data: *mut Self,
+        symbolic: *mut I::KluSymbolic,
+        numeric: *mut I::KluNumeric,
+        common: *mut I::KluCommon,
+    ) -> bool {
+        I::klu_condest(colum_offsets, data, symbolic, numeric, common)
+    }
+
+    unsafe fn klu_rgrowth<I: KluIndex>(
+        colum_offsets: *const I,
+        row_indices: *const I,
+        data: *mut Self,
+        symbolic: *mut I::KluSymbolic,
+        numeric: *mut I::KluNumeric,
+        common: *mut I::KluCommon,
+    ) -> bool {
+        I::klu_rgrowth(colum_offsets, row_indices, data, symbolic, numeric, common)
+    }
+
+    unsafe fn klu_flops<I: KluIndex>(
+        symbolic: *mut I::KluSymbolic,
+        numeric: *mut I::KluNumeric,
+        common: *mut I::KluCommon,
+    ) -> bool {
+        I::klu_flops(symbolic, numeric, common)
+    }
+
+    unsafe fn klu_extract<I: KluIndex>(
+        numeric: *mut I::KluNumeric,
+        symbolic: *mut I::KluSymbolic,
+        l_column_offsets: *mut I,
+        l_row_indices: *mut I,
+        l_data: &mut [Self],
+        u_column_offsets: *mut I,
+        u_row_indices: *mut I,
+        u_data: &mut [Self],
+        f_column_offsets: *mut I,
+        f_row_indices: *mut I,
+        f_data: &mut [Self],
+        row_permutation: *mut I,
+        column_permutation: *mut I,
+        row_scaling: *mut f64,
+        block_boundaries: *mut I,
+        common: *mut I::KluCommon,
+    ) -> bool {
+        I::klu_extract(
+            numeric,
+            symbolic,
+            l_column_offsets,
+            l_row_indices,
+            l_data.as_mut_ptr(),
+            u_column_offsets,
+            u_row_indices,
+            u_data.as_mut_ptr(),
+            f_column_offsets,
+            f_row_indices,
+            f_data.as_mut_ptr(),
+            row_permutation,
+            column_permutation,
+            row_scaling,
+            block_boundaries,
+            common,
+        )
+    }
 }
 
 impl KluData for Complex64 {
+    const IS_COMPLEX: bool = true;
+
+    fn from_parts(re: f64, im: f64) -> Self {
+        Complex64::new(re, im)
+    }
+
     unsafe fn klu_solve<I: KluIndex>(
         symbolic: *mut <I as KluIndex>::KluSymbolic,
         numeric: *mut <I as KluIndex>::KluNumeric,
@@ -303,6 +589,7 @@ impl KluData for Complex64 {
         rhs_dimension: I,
         number_rhs: I,
         rhs_data: *mut Self,
+        conjugate: bool,
         common: *mut I::KluCommon,
     ) -> bool {
         I::klu_z_tsolve(
@@ -311,6 +598,7 @@ impl KluData for Complex64 {
             rhs_dimension,
             number_rhs,
             rhs_data as *mut f64,
+            conjugate,
             common,
         )
     }
@@ -363,6 +651,96 @@ impl KluData for Complex64 {
     ) -> bool {
         I::klu_z_rcond(symbolic, numeric, common)
     }
+
+    unsafe fn klu_condest<I: KluIndex>(
+        colum_offsets: *const I,
+        data: *mut Self,
+        symbolic: *mut I::KluSymbolic,
+        numeric: *mut I::KluNumeric,
+        common: *mut I::KluCommon,
+    ) -> bool {
+        I::klu_z_condest(colum_offsets, data as *mut f64, symbolic, numeric, common)
+    }
+
+    unsafe fn klu_rgrowth<I: KluIndex>(
+        colum_offsets: *const I,
+        row_indices: *const I,
+        data: *mut Self,
+        symbolic: *mut I::KluSymbolic,
+        numeric: *mut I::KluNumeric,
+        common: *mut I::KluCommon,
+    ) -> bool {
+        I::klu_z_rgrowth(
+            colum_offsets,
+            row_indices,
+            data as *mut f64,
+            symbolic,
+            numeric,
+            common,
+        )
+    }
+
+    unsafe fn klu_flops<I: KluIndex>(
+        symbolic: *mut I::KluSymbolic,
+        numeric: *mut I::KluNumeric,
+        common: *mut I::KluCommon,
+    ) -> bool {
+        I::klu_z_flops(symbolic, numeric, common)
+    }
+
+    unsafe fn klu_extract<I: KluIndex>(
+        numeric: *mut I::KluNumeric,
+        symbolic: *mut I::KluSymbolic,
+        l_column_offsets: *mut I,
+        l_row_indices: *mut I,
+        l_data: &mut [Self],
+        u_column_offsets: *mut I,
+        u_row_indices: *mut I,
+        u_data: &mut [Self],
+        f_column_offsets: *mut I,
+        f_row_indices: *mut I,
+        f_data: &mut [Self],
+        row_permutation: *mut I,
+        column_permutation: *mut I,
+        row_scaling: *mut f64,
+        block_boundaries: *mut I,
+        common: *mut I::KluCommon,
+    ) -> bool {
+        // KLU stores the real and imaginary parts of the extracted factors in separate arrays
+        let mut l_split = [vec![0f64; l_data.len()], vec![0f64; l_data.len()]];
+        let mut u_split = [vec![0f64; u_data.len()], vec![0f64; u_data.len()]];
+        let mut f_split = [vec![0f64; f_data.len()], vec![0f64; f_data.len()]];
+        let [l_re, l_im] = &mut l_split;
+        let [u_re, u_im] = &mut u_split;
+        let [f_re, f_im] = &mut f_split;
+        let res = I::klu_z_extract(
+            numeric,
+            symbolic,
+            l_column_offsets,
+            l_row_indices,
+            l_re.as_mut_ptr(),
+            l_im.as_mut_ptr(),
+            u_column_offsets,
+            u_row_indices,
+            u_re.as_mut_ptr(),
+            u_im.as_mut_ptr(),
+            f_column_offsets,
+            f_row_indices,
+            f_re.as_mut_ptr(),
+            f_im.as_mut_ptr(),
+            row_permutation,
+            column_permutation,
+            row_scaling,
+            block_boundaries,
+            common,
+        );
+        for (dst, [re, im]) in [(l_data, l_split), (u_data, u_split), (f_data, f_split)] {
+            for ((dst, re), im) in dst.iter_mut().zip(re).zip(im) {
+                *dst = Complex64::new(re, im);
+            }
+        }
+        res
+    }
 }
 // targets where c_int != i32 are not supported
 impl KluIndex for i32 {
@@ -370,6 +748,22 @@ impl KluIndex for i32 {
     type KluNumeric = KluNumeric;
     type KluSymbolic = KluSymbolic;
 
+    const MAX: usize = i32::MAX as usize;
+
+    unsafe fn symbolic_fields<'a>(symbolic: *const Self::KluSymbolic) -> &'a KluSymbolicT<Self> {
+        &*symbolic
+    }
+
+    unsafe fn symbolic_fields_mut<'a>(
+        symbolic: *mut Self::KluSymbolic,
+    ) -> &'a mut KluSymbolicT<Self> {
+        &mut *symbolic
+    }
+
+    unsafe fn numeric_fields<'a>(numeric: *mut Self::KluNumeric) -> &'a KluNumericT<Self> {
+        &*numeric
+    }
+
     fn from_usize(val: usize) -> Self {
         debug_assert!(val <= Self::MAX as usize);
         val as Self
@@ -380,6 +774,10 @@ impl KluIndex for i32 {
         self as usize
     }
 
+    fn into_i64(self) -> i64 {
+        self as i64
+    }
+
     unsafe fn klu_defaults(common: *mut Self::KluCommon) -> Self {
         klu_defaults(common)
     }
@@ -398,6 +796,24 @@ impl KluIndex for i32 {
         )
     }
 
+    unsafe fn klu_analyze_given(
+        dim: Self,
+        colum_offsets: *const Self,
+        row_indices: *const Self,
+        row_permutation: *const Self,
+        column_permutation: *const Self,
+        common: *mut Self::KluCommon,
+    ) -> *mut Self::KluSymbolic {
+        klu_analyze_given(
+            dim,
+            colum_offsets as *mut Self,
+            row_indices as *mut Self,
+            row_permutation as *mut Self,
+            column_permutation as *mut Self,
+            common,
+        )
+    }
+
     unsafe fn klu_free_symbolic(
         symbolic: *mut *mut Self::KluSymbolic,
         common: *mut Self::KluCommon,
@@ -535,6 +951,7 @@ impl KluIndex for i32 {
         rhs_dimension: Self,
         number_rhs: Self,
         rhs_data: *mut f64,
+        conjugate: bool,
         common: *mut Self::KluCommon,
     ) -> bool {
         klu_z_tsolve(
@@ -543,7 +960,7 @@ impl KluIndex for i32 {
             rhs_dimension,
             number_rhs,
             rhs_data,
-            0,
+            conjugate as Self,
             common,
         ) != 0
     }
@@ -563,26 +980,73 @@ impl KluIndex for i32 {
         }
     }
 
-    fn check_status(common: &Self::KluCommon) {
-        match common.status {
-            -2 => unreachable!("KLU error: OUT OF MEMORY"),
-            -3 => unreachable!("KLU error: INVALID"),
-            -4 => unreachable!("KLU error: TOO LARGE"),
-            code @ (Self::MIN..=-5 | -1) => {
-                unreachable!("KLU failed with unkown errorcode {}", code)
-            }
-            _ => (),
-        }
+    fn check_status(common: &Self::KluCommon) -> Result<(), KluError> {
+        KluError::from_status(common.status as i64, common.singular_col as i64)
     }
 
     fn get_rcond(common: &Self::KluCommon) -> f64 {
         common.rcond
     }
 
+    fn get_condest(common: &Self::KluCommon) -> f64 {
+        common.condest
+    }
+
+    fn get_rgrowth(common: &Self::KluCommon) -> f64 {
+        common.rgrowth
+    }
+
+    fn get_flops(common: &Self::KluCommon) -> f64 {
+        common.flops
+    }
+
+    fn get_noffdiag(common: &Self::KluCommon) -> Self {
+        common.noffdiag
+    }
+
     fn is_singular(common: &Self::KluCommon) -> bool {
         common.status == 1
     }
 
+    fn get_options(common: &Self::KluCommon) -> KluOptions {
+        KluOptions {
+            tol: common.tol,
+            memgrow: common.memgrow,
+            initmem_amd: common.initmem_amd,
+            initmem: common.initmem,
+            maxwork: common.maxwork,
+            btf: common.btf != 0,
+            ordering: common.ordering as i64,
+            scale: common.scale as i64,
+            halt_if_singular: common.halt_if_singular != 0,
+        }
+    }
+
+    fn set_options(common: &mut Self::KluCommon, options: &KluOptions) {
+        common.tol = options.tol;
+        common.memgrow = options.memgrow;
+        common.initmem_amd = options.initmem_amd;
+        common.initmem = options.initmem;
+        common.maxwork = options.maxwork;
+        common.btf = options.btf as Self;
+        common.ordering = options.ordering as Self;
+        common.scale = options.scale as Self;
+        common.halt_if_singular = options.halt_if_singular as Self;
+    }
+
+    fn user_data(common: &Self::KluCommon) -> *mut c_void {
+        common.user_data
+    }
+
+    fn set_user_order(
+        common: &mut Self::KluCommon,
+        user_order: Option<KluUserOrder<Self>>,
+        user_data: *mut c_void,
+    ) {
+        common.user_order = user_order;
+        common.user_data = user_data;
+    }
+
     unsafe fn klu_rcond(
         symbolic: *mut Self::KluSymbolic,
         numeric: *mut Self::KluNumeric,
@@ -598,6 +1062,160 @@ impl KluIndex for i32 {
     ) -> bool {
         klu_z_rcond(symbolic, numeric, common) != 0
     }
+
+    unsafe fn klu_condest(
+        colum_offsets: *const Self,
+        data: *mut f64,
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_condest(colum_offsets as *mut Self, data, symbolic, numeric, common) != 0
+    }
+
+    unsafe fn klu_rgrowth(
+        colum_offsets: *const Self,
+        row_indices: *const Self,
+        data: *mut f64,
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_rgrowth(
+            colum_offsets as *mut Self,
+            row_indices as *mut Self,
+            data,
+            symbolic,
+            numeric,
+            common,
+        ) != 0
+    }
+
+    unsafe fn klu_flops(
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_flops(symbolic, numeric, common) != 0
+    }
+
+    unsafe fn klu_z_condest(
+        colum_offsets: *const Self,
+        data: *mut f64,
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_z_condest(colum_offsets as *mut Self, data, symbolic, numeric, common) != 0
+    }
+
+    unsafe fn klu_z_rgrowth(
+        colum_offsets: *const Self,
+        row_indices: *const Self,
+        data: *mut f64,
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_z_rgrowth(
+            colum_offsets as *mut Self,
+            row_indices as *mut Self,
+            data,
+            symbolic,
+            numeric,
+            common,
+        ) != 0
+    }
+
+    unsafe fn klu_z_flops(
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_z_flops(symbolic, numeric, common) != 0
+    }
+
+    unsafe fn klu_extract(
+        numeric: *mut Self::KluNumeric,
+        symbolic: *mut Self::KluSymbolic,
+        l_column_offsets: *mut Self,
+        l_row_indices: *mut Self,
+        l_data: *mut f64,
+        u_column_offsets: *mut Self,
+        u_row_indices: *mut Self,
+        u_data: *mut f64,
+        f_column_offsets: *mut Self,
+        f_row_indices: *mut Self,
+        f_data: *mut f64,
+        row_permutation: *mut Self,
+        column_permutation: *mut Self,
+        row_scaling: *mut f64,
+        block_boundaries: *mut Self,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_extract(
+            numeric,
+            symbolic,
+            l_column_offsets,
+            l_row_indices,
+            l_data,
+            u_column_offsets,
+            u_row_indices,
+            u_data,
+            f_column_offsets,
+            f_row_indices,
+            f_data,
+            row_permutation,
+            column_permutation,
+            row_scaling,
+            block_boundaries,
+            common,
+        ) != 0
+    }
+
+    unsafe fn klu_z_extract(
+        numeric: *mut Self::KluNumeric,
+        symbolic: *mut Self::KluSymbolic,
+        l_column_offsets: *mut Self,
+        l_row_indices: *mut Self,
+        l_data: *mut f64,
+        l_imag: *mut f64,
+        u_column_offsets: *mut Self,
+        u_row_indices: *mut Self,
+        u_data: *mut f64,
+        u_imag: *mut f64,
+        f_column_offsets: *mut Self,
+        f_row_indices: *mut Self,
+        f_data: *mut f64,
+        f_imag: *mut f64,
+        row_permutation: *mut Self,
+        column_permutation: *mut Self,
+        row_scaling: *mut f64,
+        block_boundaries: *mut Self,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_z_extract(
+            numeric,
+            symbolic,
+            l_column_offsets,
+            l_row_indices,
+            l_data,
+            l_imag,
+            u_column_offsets,
+            u_row_indices,
+            u_data,
+            u_imag,
+            f_column_offsets,
+            f_row_indices,
+            f_data,
+            f_imag,
+            row_permutation,
+            column_permutation,
+            row_scaling,
+            block_boundaries,
+            common,
+        ) != 0
+    }
 }
 
 // Klu uses conditions to ensure its long is always 64 bit
@@ -606,16 +1224,36 @@ impl KluIndex for i64 {
     type KluNumeric = KluLNumeric;
     type KluSymbolic = KluLSymbolic;
 
+    const MAX: usize = i64::MAX as usize;
+
+    unsafe fn symbolic_fields<'a>(symbolic: *const Self::KluSymbolic) -> &'a KluSymbolicT<Self> {
+        &*symbolic
+    }
+
+    unsafe fn symbolic_fields_mut<'a>(
+        symbolic: *mut Self::KluSymbolic,
+    ) -> &'a mut KluSymbolicT<Self> {
+        &mut *symbolic
+    }
+
+    unsafe fn numeric_fields<'a>(numeric: *mut Self::KluNumeric) -> &'a KluNumericT<Self> {
+        &*numeric
+    }
+
     fn from_usize(val: usize) -> Self {
         debug_assert!(val < Self::MAX as usize);
         val as Self
     }
 
     fn into_usize(self) -> usize {
-        debug_assert!(self > 0);
+        debug_assert!(self >= 0);
         self as usize
     }
 
+    fn into_i64(self) -> i64 {
+        self
+    }
+
     unsafe fn klu_defaults(common: *mut Self::KluCommon) -> Self {
         klu_l_defaults(common)
     }
@@ -634,6 +1272,24 @@ impl KluIndex for i64 {
         )
     }
 
+    unsafe fn klu_analyze_given(
+        dim: Self,
+        colum_offsets: *const Self,
+        row_indices: *const Self,
+        row_permutation: *const Self,
+        column_permutation: *const Self,
+        common: *mut Self::KluCommon,
+    ) -> *mut Self::KluSymbolic {
+        klu_l_analyze_given(
+            dim,
+            colum_offsets as *mut Self,
+            row_indices as *mut Self,
+            row_permutation as *mut Self,
+            column_permutation as *mut Self,
+            common,
+        )
+    }
+
     unsafe fn klu_free_symbolic(
         symbolic: *mut *mut Self::KluSymbolic,
         common: *mut Self::KluCommon,
@@ -771,6 +1427,7 @@ impl KluIndex for i64 {
         rhs_dimension: Self,
         number_rhs: Self,
         rhs_data: *mut f64,
+        conjugate: bool,
         common: *mut Self::KluCommon,
     ) -> bool {
         klu_zl_tsolve(
@@ -779,7 +1436,7 @@ impl KluIndex for i64 {
             rhs_dimension,
             number_rhs,
             rhs_data,
-            0,
+            conjugate as Self,
             common,
         ) != 0
     }
@@ -799,27 +1456,73 @@ impl KluIndex for i64 {
         }
     }
 
-    fn check_status(common: &Self::KluCommon) {
-        match common.status {
-            0 => (),
-            -1 => unreachable!("KLU failed with unkown errorcode -1"),
-            -2 => unreachable!("KLU error: OUT OF MEMORY"),
-            -3 => unreachable!("KLU error: INVALID"),
-            -4 => unreachable!("KLU error: TOO LARGE"),
-            code @ Self::MIN..=-5 => unreachable!("KLU failed with unkown errorcode {}", code),
-            1 => unreachable!("Singular matrix!"),
-            code => unreachable!("Unkown warning {code}"),
-        }
+    fn check_status(common: &Self::KluCommon) -> Result<(), KluError> {
+        KluError::from_status(common.status, common.singular_col)
     }
 
     fn get_rcond(common: &Self::KluCommon) -> f64 {
         common.rcond
     }
 
+    fn get_condest(common: &Self::KluCommon) -> f64 {
+        common.condest
+    }
+
+    fn get_rgrowth(common: &Self::KluCommon) -> f64 {
+        common.rgrowth
+    }
+
+    fn get_flops(common: &Self::KluCommon) -> f64 {
+        common.flops
+    }
+
+    fn get_noffdiag(common: &Self::KluCommon) -> Self {
+        common.noffdiag
+    }
+
     fn is_singular(common: &Self::KluCommon) -> bool {
         common.status == 1
     }
 
+    fn get_options(common: &Self::KluCommon) -> KluOptions {
+        KluOptions {
+            tol: common.tol,
+            memgrow: common.memgrow,
+            initmem_amd: common.initmem_amd,
+            initmem: common.initmem,
+            maxwork: common.maxwork,
+            btf: common.btf != 0,
+            ordering: common.ordering,
+            scale: common.scale,
+            halt_if_singular: common.halt_if_singular != 0,
+        }
+    }
+
+    fn set_options(common: &mut Self::KluCommon, options: &KluOptions) {
+        common.tol = options.tol;
+        common.memgrow = options.memgrow;
+        common.initmem_amd = options.initmem_amd;
+        common.initmem = options.initmem;
+        common.maxwork = options.maxwork;
+        common.btf = options.btf as Self;
+        common.ordering = options.ordering;
+        common.scale = options.scale;
+        common.halt_if_singular = options.halt_if_singular as Self;
+    }
+
+    fn user_data(common: &Self::KluCommon) -> *mut c_void {
+        common.user_data
+    }
+
+    fn set_user_order(
+        common: &mut Self::KluCommon,
+        user_order: Option<KluUserOrder<Self>>,
+        user_data: *mut c_void,
+    ) {
+        common.user_order = user_order;
+        common.user_data = user_data;
+    }
+
     unsafe fn klu_rcond(
         symbolic: *mut Self::KluSymbolic,
         numeric: *mut Self::KluNumeric,
@@ -835,4 +1538,158 @@ impl KluIndex for i64 {
     ) -> bool {
         klu_zl_rcond(symbolic, numeric, common) != 0
     }
+
+    unsafe fn klu_condest(
+        colum_offsets: *const Self,
+        data: *mut f64,
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_l_condest(colum_offsets as *mut Self, data, symbolic, numeric, common) != 0
+    }
+
+    unsafe fn klu_rgrowth(
+        colum_offsets: *const Self,
+        row_indices: *const Self,
+        data: *mut f64,
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_l_rgrowth(
+            colum_offsets as *mut Self,
+            row_indices as *mut Self,
+            data,
+            symbolic,
+            numeric,
+            common,
+        ) != 0
+    }
+
+    unsafe fn klu_flops(
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_l_flops(symbolic, numeric, common) != 0
+    }
+
+    unsafe fn klu_z_condest(
+        colum_offsets: *const Self,
+        data: *mut f64,
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_zl_condest(colum_offsets as *mut Self, data, symbolic, numeric, common) != 0
+    }
+
+    unsafe fn klu_z_rgrowth(
+        colum_offsets: *const Self,
+        row_indices: *const Self,
+        data: *mut f64,
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_zl_rgrowth(
+            colum_offsets as *mut Self,
+            row_indices as *mut Self,
+            data,
+            symbolic,
+            numeric,
+            common,
+        ) != 0
+    }
+
+    unsafe fn klu_z_flops(
+        symbolic: *mut Self::KluSymbolic,
+        numeric: *mut Self::KluNumeric,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_zl_flops(symbolic, numeric, common) != 0
+    }
+
+    unsafe fn klu_extract(
+        numeric: *mut Self::KluNumeric,
+        symbolic: *mut Self::KluSymbolic,
+        l_column_offsets: *mut Self,
+        l_row_indices: *mut Self,
+        l_data: *mut f64,
+        u_column_offsets: *mut Self,
+        u_row_indices: *mut Self,
+        u_data: *mut f64,
+        f_column_offsets: *mut Self,
+        f_row_indices: *mut Self,
+        f_data: *mut f64,
+        row_permutation: *mut Self,
+        column_permutation: *mut Self,
+        row_scaling: *mut f64,
+        block_boundaries: *mut Self,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_l_extract(
+            numeric,
+            symbolic,
+            l_column_offsets,
+            l_row_indices,
+            l_data,
+            u_column_offsets,
+            u_row_indices,
+            u_data,
+            f_column_offsets,
+            f_row_indices,
+            f_data,
+            row_permutation,
+            column_permutation,
+            row_scaling,
+            block_boundaries,
+            common,
+        ) != 0
+    }
+
+    unsafe fn klu_z_extract(
+        numeric: *mut Self::KluNumeric,
+        symbolic: *mut Self::KluSymbolic,
+        l_column_offsets: *mut Self,
+        l_row_indices: *mut Self,
+        l_data: *mut f64,
+        l_imag: *mut f64,
+        u_column_offsets: *mut Self,
+        u_row_indices: *mut Self,
+        u_data: *mut f64,
+        u_imag: *mut f64,
+        f_column_offsets: *mut Self,
+        f_row_indices: *mut Self,
+        f_data: *mut f64,
+        f_imag: *mut f64,
+        row_permutation: *mut Self,
+        column_permutation: *mut Self,
+        row_scaling: *mut f64,
+        block_boundaries: *mut Self,
+        common: *mut Self::KluCommon,
+    ) -> bool {
+        klu_zl_extract(
+            numeric,
+            symbolic,
+            l_column_offsets,
+            l_row_indices,
+            l_data,
+            l_imag,
+            u_column_offsets,
+            u_row_indices,
+            u_data,
+            u_imag,
+            f_column_offsets,
+            f_row_indices,
+            f_data,
+            f_imag,
+            row_permutation,
+            column_permutation,
+            row_scaling,
+            block_boundaries,
+            common,
+        ) != 0
+    }
 }
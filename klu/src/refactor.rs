@@ -0,0 +1,219 @@
+use std::num::NonZeroUsize;
+
+use crate::SettingError;
+
+/// Decides whether [`lu_factorize`](crate::FixedKluMatrix::lu_factorize) reuses the pivots of
+/// the previous factorization (`klu_refactor`) or performs a full factorization (`klu_factor`).
+///
+/// Refactoring is much faster than a full factorization but the reused pivots can be numerically
+/// poor for the new values. A refactorization that violates one of the limits of the policy is
+/// discarded and replaced by a full factorization.
+///
+/// The default policy never refactors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RefactorPolicy {
+    refactor: bool,
+    min_rcond: Option<f64>,
+    max_pivot_growth: Option<f64>,
+    full_factor_every: Option<NonZeroUsize>,
+}
+
+impl RefactorPolicy {
+    /// Always performs a full factorization
+    pub const fn never() -> Self {
+        Self {
+            refactor: false,
+            min_rcond: None,
+            max_pivot_growth: None,
+            full_factor_every: None,
+        }
+    }
+
+    /// Refactors whenever the matrix was factorized before and `klu_refactor` succeeds.
+    /// The limits below can only be set on policies created with this function.
+    pub const fn always() -> Self {
+        Self {
+            refactor: true,
+            ..Self::never()
+        }
+    }
+
+    /// Whether refactoring is attempted at all
+    pub fn refactor(&self) -> bool {
+        self.refactor
+    }
+
+    /// Refactorizations with a reciprocal condition estimate (`klu_rcond`) below this value are
+    /// discarded.
+    pub fn min_rcond(&self) -> Option<f64> {
+        self.min_rcond
+    }
+
+    /// Discards refactorizations whose reciprocal condition estimate is below `min_rcond`
+    /// (see [`min_rcond`](Self::min_rcond)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_rcond` is negative or not finite or if the policy never refactors.
+    /// See [`try_with_min_rcond`](Self::try_with_min_rcond) for a fallible version.
+    pub fn with_min_rcond(self, min_rcond: f64) -> Self {
+        self.try_with_min_rcond(min_rcond)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Discards refactorizations whose reciprocal condition estimate is below `min_rcond`
+    /// (see [`min_rcond`](Self::min_rcond)).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SettingError`] if `min_rcond` is negative or not finite or if the policy never
+    /// refactors
+    pub fn try_with_min_rcond(mut self, min_rcond: f64) -> Result<Self, SettingError> {
+        self.check_refactor("min_rcond", min_rcond)?;
+        if !(min_rcond.is_finite() && min_rcond >= 0.0) {
+            return Err(SettingError {
+                setting: "min_rcond",
+                value: min_rcond,
+                expected: "finite and not negative",
+            });
+        }
+        self.min_rcond = Some(min_rcond);
+        Ok(self)
+    }
+
+    /// Refactorizations with a pivot growth (`1 / klu_rgrowth`, the inverse of
+    /// [`reciprocal_pivot_growth`](crate::Factorization::reciprocal_pivot_growth)) above this
+    /// value are discarded.
+    pub fn max_pivot_growth(&self) -> Option<f64> {
+        self.max_pivot_growth
+    }
+
+    /// Discards refactorizations whose pivot growth exceeds `max_pivot_growth` (see
+    /// [`max_pivot_growth`](Self::max_pivot_growth)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_pivot_growth` is smaller than 1 or not finite or if the policy never
+    /// refactors. See [`try_with_max_pivot_growth`](Self::try_with_max_pivot_growth) for a
+    /// fallible version.
+    pub fn with_max_pivot_growth(self, max_pivot_growth: f64) -> Self {
+        self.try_with_max_pivot_growth(max_pivot_growth)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Discards refactorizations whose pivot growth exceeds `max_pivot_growth` (see
+    /// [`max_pivot_growth`](Self::max_pivot_growth)).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SettingError`] if `max_pivot_growth` is smaller than 1 or not finite or if the
+    /// policy never refactors
+    pub fn try_with_max_pivot_growth(
+        mut self,
+        max_pivot_growth: f64,
+    ) -> Result<Self, SettingError> {
+        self.check_refactor("max_pivot_growth", max_pivot_growth)?;
+        if !(max_pivot_growth.is_finite() && max_pivot_growth >= 1.0) {
+            return Err(SettingError {
+                setting: "max_pivot_growth",
+                value: max_pivot_growth,
+                expected: "finite and at least 1",
+            });
+        }
+        self.max_pivot_growth = Some(max_pivot_growth);
+        Ok(self)
+    }
+
+    /// Every `n`-th factorization is a full factorization even if the refactorization would be
+    /// accepted. `None` if the number of consecutive refactorizations is not limited.
+    pub fn full_factor_every(&self) -> Option<usize> {
+        self.full_factor_every.map(NonZeroUsize::get)
+    }
+
+    /// Forces a full factorization every `n` factorizations (see
+    /// [`full_factor_every`](Self::full_factor_every)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero or if the policy never refactors.
+    /// See [`try_with_full_factor_every`](Self::try_with_full_factor_every) for a fallible
+    /// version.
+    pub fn with_full_factor_every(self, n: usize) -> Self {
+        self.try_with_full_factor_every(n)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Forces a full factorization every `n` factorizations (see
+    /// [`full_factor_every`](Self::full_factor_every)).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SettingError`] if `n` is zero or if the policy never refactors
+    pub fn try_with_full_factor_every(mut self, n: usize) -> Result<Self, SettingError> {
+        self.check_refactor("full_factor_every", n as f64)?;
+        let Some(n) = NonZeroUsize::new(n) else {
+            return Err(SettingError {
+                setting: "full_factor_every",
+                value: 0.0,
+                expected: "at least 1",
+            });
+        };
+        self.full_factor_every = Some(n);
+        Ok(self)
+    }
+
+    /// The limits would be silently ignored by a policy that never refactors
+    fn check_refactor(&self, limit: &'static str, value: f64) -> Result<(), SettingError> {
+        if !self.refactor {
+            return Err(SettingError {
+                setting: limit,
+                value,
+                expected: "set on a policy that refactors (RefactorPolicy::always)",
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for RefactorPolicy {
+    fn default() -> Self {
+        Self::never()
+    }
+}
+
+/// The result of [`lu_factorize`](crate::FixedKluMatrix::lu_factorize), see
+/// [`Factorization::outcome`](crate::Factorization::outcome)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FactorOutcome {
+    /// The pivots of the previous factorization were reused
+    Refactored,
+    /// A full factorization with partial pivoting was performed
+    FullFactor { reason: FullFactorReason },
+    /// A full factorization was performed but the matrix is singular. `column` is the first
+    /// column (in the original matrix) where a zero pivot was encountered.
+    ///
+    /// Only reported if [`KluSettings::halt_if_singular`](crate::KluSettings::halt_if_singular) is
+    /// unset, otherwise the matrix is left unfactorized and
+    /// [`KluError::Singular`](crate::KluError::Singular) is returned instead.
+    Singular { column: usize },
+}
+
+/// Why [`lu_factorize`](crate::FixedKluMatrix::lu_factorize) performed a full factorization
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FullFactorReason {
+    /// The matrix was not factorized before
+    Initial,
+    /// The policy does not allow refactoring
+    Disabled,
+    /// The number of consecutive refactorizations reached
+    /// [`full_factor_every`](RefactorPolicy::full_factor_every)
+    Scheduled,
+    /// `klu_refactor` failed (for example because it encountered a zero pivot)
+    RefactorFailed,
+    /// The reciprocal condition estimate of the refactorization was below
+    /// [`min_rcond`](RefactorPolicy::min_rcond)
+    LowRcond { rcond: f64 },
+    /// The pivot growth of the refactorization exceeded
+    /// [`max_pivot_growth`](RefactorPolicy::max_pivot_growth)
+    PivotGrowth { growth: f64 },
+}
@@ -0,0 +1,189 @@
+use std::rc::Rc;
+
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    is_sorted, FixedKluMatrix, KluData, KluIndex, KluMatrixSpec, KluOrdering, KluScaling,
+    KluSettings, KluSpecRef,
+};
+
+/// The serialized configuration of [`KluSettings`]. The KLU status is not serialized.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "KluSettings")]
+struct SettingsRepr {
+    tol: f64,
+    memgrow: f64,
+    initmem_amd: f64,
+    initmem: f64,
+    maxwork: f64,
+    btf: bool,
+    ordering: KluOrdering,
+    scaling: KluScaling,
+    halt_if_singular: bool,
+}
+
+impl<I: KluIndex> Serialize for KluSettings<I> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let ordering = self.ordering();
+        if ordering == KluOrdering::User {
+            return Err(S::Error::custom("user orderings can not be serialized"));
+        }
+        SettingsRepr {
+            tol: self.tol(),
+            memgrow: self.memgrow(),
+            initmem_amd: self.initmem_amd(),
+            initmem: self.initmem(),
+            maxwork: self.maxwork(),
+            btf: self.btf(),
+            ordering,
+            scaling: self.scaling(),
+            halt_if_singular: self.halt_if_singular(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, I: KluIndex> Deserialize<'de> for KluSettings<I> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = SettingsRepr::deserialize(deserializer)?;
+        if repr.ordering == KluOrdering::User {
+            return Err(D::Error::custom("user orderings can not be deserialized"));
+        }
+
+        let settings = (|| {
+            KluSettings::new()
+                .try_with_tol(repr.tol)?
+                .try_with_memgrow(repr.memgrow)?
+                .try_with_initmem_amd(repr.initmem_amd)?
+                .try_with_initmem(repr.initmem)?
+                .try_with_maxwork(repr.maxwork)?
+                .try_with_ordering(repr.ordering)?
+                .try_with_scaling(repr.scaling)
+        })();
+        Ok(settings
+            .map_err(D::Error::custom)?
+            .with_btf(repr.btf)
+            .with_halt_if_singular(repr.halt_if_singular))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename = "KluMatrixSpec")]
+struct SpecRef<'a, I: KluIndex> {
+    dim: usize,
+    column_offsets: &'a [I],
+    row_indices: &'a [I],
+    transposed: bool,
+    settings: &'a KluSettings<I>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "KluMatrixSpec")]
+struct SpecRepr<I: KluIndex> {
+    dim: usize,
+    column_offsets: Vec<I>,
+    row_indices: Vec<I>,
+    transposed: bool,
+    settings: KluSettings<I>,
+}
+
+/// The pattern and the settings of the spec are serialized. The symbolic analysis is recomputed
+/// during deserialization.
+impl<I: KluIndex + Serialize> Serialize for KluMatrixSpec<I> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SpecRef {
+            dim: self.dim(),
+            column_offsets: &self.column_offsets,
+            row_indices: &self.row_indices,
+            transposed: self.transposed,
+            settings: &self.settings,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, I: KluIndex + Deserialize<'de>> Deserialize<'de> for KluMatrixSpec<I> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = SpecRepr::<I>::deserialize(deserializer)?;
+        // serialized specs always store sorted rows. `from_compressed` would sort them without
+        // permuting the values of a serialized matrix accordingly
+        // (invalid offsets are reported by `from_compressed`)
+        let offset = |offset: I| usize::try_from(offset.into_i64()).ok();
+        let unsorted = repr.column_offsets.windows(2).position(|offsets| {
+            let rows = offset(offsets[0])
+                .zip(offset(offsets[1]))
+                .and_then(|(start, end)| repr.row_indices.get(start..end));
+            rows.is_some_and(|rows| !is_sorted(rows))
+        });
+        if let Some(column) = unsorted {
+            return Err(D::Error::custom(format!(
+                "the rows of column {column} are not sorted"
+            )));
+        }
+        KluMatrixSpec::from_compressed(
+            repr.dim,
+            repr.column_offsets,
+            repr.row_indices,
+            repr.settings,
+            repr.transposed,
+        )
+        .map_err(D::Error::custom)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename = "FixedKluMatrix")]
+struct MatrixRef<'a, I: KluIndex, D> {
+    spec: &'a KluMatrixSpec<I>,
+    values: Vec<D>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "FixedKluMatrix")]
+struct MatrixRepr<I: KluIndex, D> {
+    spec: KluMatrixSpec<I>,
+    values: Vec<D>,
+}
+
+/// The spec and the current values of the matrix are serialized.
+/// The factorization is not serialized.
+impl<I, D, S> Serialize for FixedKluMatrix<I, D, S>
+where
+    I: KluIndex + Serialize,
+    D: KluData + Serialize,
+    S: KluSpecRef<I>,
+{
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        MatrixRef {
+            spec: &self.spec,
+            values: self.data().iter().map(|val| val.get()).collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, I, D> Deserialize<'de> for FixedKluMatrix<I, D>
+where
+    I: KluIndex + Deserialize<'de>,
+    D: KluData + Deserialize<'de>,
+{
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let repr = MatrixRepr::<I, D>::deserialize(deserializer)?;
+        if repr.values.len() != repr.spec.entry_cnt() {
+            return Err(De::Error::custom(format!(
+                "expected {} values but found {}",
+                repr.spec.entry_cnt(),
+                repr.values.len()
+            )));
+        }
+        let matrix = Rc::new(repr.spec)
+            .create_matrix()
+            .ok_or_else(|| De::Error::custom("matrix without entries"))?;
+        for (entry, val) in matrix.data().iter().zip(repr.values) {
+            entry.set(val);
+        }
+        Ok(matrix)
+    }
+}
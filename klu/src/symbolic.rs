@@ -0,0 +1,363 @@
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+use std::slice;
+
+use crate::ordering::is_permutation;
+use crate::{GivenPermutations, KluError, KluIndex, KluMatrixSpec, KluSettings};
+
+/// Identifies files written by [`SymbolicOrdering::write`]
+const MAGIC: [u8; 8] = *b"KLUSYMB\0";
+/// The version of the on-disk format. Files with a different version are rejected by
+/// [`SymbolicOrdering::read`].
+const FORMAT_VERSION: u32 = 1;
+
+/// The result of the symbolic analysis of a [`KluMatrixSpec`]: the row and column permutations,
+/// the block structure of the BTF form and the fill estimates of KLU.
+///
+/// An ordering is obtained with [`KluMatrixSpec::export_ordering`] and can be stored with
+/// [`write`](Self::write). A spec with the same pattern can then be constructed with
+/// [`KluMatrixSpec::with_ordering`] without repeating the analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolicOrdering<I> {
+    dim: usize,
+    entry_cnt: usize,
+    /// A hash of the pattern the ordering was computed for
+    pattern_hash: u64,
+    row_permutation: Vec<I>,
+    column_permutation: Vec<I>,
+    block_boundaries: Vec<I>,
+    /// The estimated number of entries in `L` of each block (negative if not estimated)
+    block_lnz: Vec<f64>,
+    lnz: f64,
+    unz: f64,
+    symmetry: f64,
+    est_flops: f64,
+    /// The ordering method reported by KLU (0: AMD, 1: COLAMD, 2: given, 3: user)
+    method: u64,
+    do_btf: bool,
+}
+
+impl<I: KluIndex> SymbolicOrdering<I> {
+    /// The number of rows/columns of the matrix the ordering was computed for
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Row `k` of the permuted matrix is row `row_permutation()[k]` of `A`
+    pub fn row_permutation(&self) -> &[I] {
+        &self.row_permutation
+    }
+
+    /// Column `k` of the permuted matrix is column `column_permutation()[k]` of `A`
+    pub fn column_permutation(&self) -> &[I] {
+        &self.column_permutation
+    }
+
+    /// Block `b` of the block triangular form spans rows/columns
+    /// `block_boundaries()[b]..block_boundaries()[b+1]`
+    pub fn block_boundaries(&self) -> &[I] {
+        &self.block_boundaries
+    }
+
+    /// The number of diagonal blocks of the block triangular form
+    pub fn nblocks(&self) -> usize {
+        self.block_lnz.len()
+    }
+
+    /// Writes the ordering in a versioned binary format.
+    ///
+    /// The file starts with the magic bytes `KLUSYMB\0` followed by the format version as a
+    /// 32 bit integer. All following values are stored as 64 bit integers or floats so that
+    /// files can be read with either index type. All values are little endian.
+    /// The output is not buffered so `writer` should usually be a
+    /// [`BufWriter`](std::io::BufWriter).
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        let header = [
+            self.dim as u64,
+            self.entry_cnt as u64,
+            self.nblocks() as u64,
+            self.pattern_hash,
+            self.method,
+            self.do_btf as u64,
+        ];
+        for val in header {
+            writer.write_all(&val.to_le_bytes())?;
+        }
+        for val in [self.lnz, self.unz, self.symmetry, self.est_flops] {
+            writer.write_all(&val.to_le_bytes())?;
+        }
+        let indices = self
+            .row_permutation
+            .iter()
+            .chain(&self.column_permutation)
+            .chain(&self.block_boundaries);
+        for idx in indices {
+            writer.write_all(&(idx.into_usize() as u64).to_le_bytes())?;
+        }
+        for val in &self.block_lnz {
+            writer.write_all(&val.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reads an ordering written by [`write`](Self::write).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`InvalidData`](io::ErrorKind::InvalidData) if the file was not
+    /// written by [`write`](Self::write), uses a different version of the format, does not fit
+    /// into the index type or does not describe a valid ordering.
+    pub fn read(mut reader: impl Read) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(invalid_data("not a KLU symbolic ordering".to_owned()));
+        }
+        let mut version = [0; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != FORMAT_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported format version {version} (expected {FORMAT_VERSION})"
+            )));
+        }
+
+        let dim = read_size::<I>(&mut reader)?;
+        let entry_cnt = read_size::<I>(&mut reader)?;
+        let nblocks = read_size::<I>(&mut reader)?;
+        let pattern_hash = read_u64(&mut reader)?;
+        let method = read_u64(&mut reader)?;
+        let do_btf = read_u64(&mut reader)? != 0;
+        let lnz = read_f64(&mut reader)?;
+        let unz = read_f64(&mut reader)?;
+        let symmetry = read_f64(&mut reader)?;
+        let est_flops = read_f64(&mut reader)?;
+        if dim == 0 || nblocks == 0 || nblocks > dim || method > 3 {
+            return Err(invalid_data("invalid header".to_owned()));
+        }
+
+        let row_permutation = read_indices(&mut reader, dim, dim)?;
+        let column_permutation = read_indices(&mut reader, dim, dim)?;
+        let block_boundaries = read_indices(&mut reader, nblocks + 1, dim + 1)?;
+        let block_lnz = (0..nblocks)
+            .map(|_| read_f64(&mut reader))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let ordering = Self {
+            dim,
+            entry_cnt,
+            pattern_hash,
+            row_permutation,
+            column_permutation,
+            block_boundaries,
+            block_lnz,
+            lnz,
+            unz,
+            symmetry,
+            est_flops,
+            method,
+            do_btf,
+        };
+        if !ordering.is_valid() {
+            return Err(invalid_data("invalid symbolic ordering".to_owned()));
+        }
+        Ok(ordering)
+    }
+
+    /// Checks the invariants KLU relies upon that do not depend on the pattern
+    fn is_valid(&self) -> bool {
+        let zero = I::from_usize(0);
+        is_permutation(&self.row_permutation)
+            && is_permutation(&self.column_permutation)
+            && self.block_boundaries[0] == zero
+            && self.block_boundaries[self.nblocks()] == I::from_usize(self.dim)
+            && self
+                .block_boundaries
+                .windows(2)
+                .all(|block| block[0] < block[1])
+            && !self.block_lnz.iter().any(|lnz| lnz.is_nan())
+    }
+
+    /// Checks that the pattern of `spec` permuted by this ordering is block upper triangular.
+    ///
+    /// # Returns
+    ///
+    /// The number of entries in the off-diagonal blocks and the size of the largest block or
+    /// `None` if an entry is below the diagonal blocks
+    fn check_blocks(&self, spec: &KluMatrixSpec<I>) -> Option<(usize, usize)> {
+        let mut inverse_row_permutation = vec![0; self.dim];
+        for (k, &row) in self.row_permutation.iter().enumerate() {
+            inverse_row_permutation[row.into_usize()] = k;
+        }
+
+        let mut nzoff = 0;
+        let mut maxblock = 1;
+        for block in self.block_boundaries.windows(2) {
+            let (start, end) = (block[0].into_usize(), block[1].into_usize());
+            maxblock = maxblock.max(end - start);
+            for &column in &self.column_permutation[start..end] {
+                let column = column.into_usize();
+                let rows = &spec.row_indices[spec.column_offsets[column].into_usize()
+                    ..spec.column_offsets[column + 1].into_usize()];
+                for &row in rows {
+                    let row = inverse_row_permutation[row.into_usize()];
+                    if row >= end {
+                        return None;
+                    }
+                    if row < start {
+                        nzoff += 1;
+                    }
+                }
+            }
+        }
+        Some((nzoff, maxblock))
+    }
+}
+
+impl<I: KluIndex> KluMatrixSpec<I> {
+    /// Exports the result of the symbolic analysis so that it can be restored for a spec with the
+    /// same pattern with [`with_ordering`](Self::with_ordering).
+    ///
+    /// # Returns
+    ///
+    /// The ordering or `None` if the spec has no symbolic analysis (because the analysis failed)
+    pub fn export_ordering(&self) -> Option<SymbolicOrdering<I>> {
+        let symbolic = self.symbolic()?;
+        let dim = self.dim();
+        let nblocks = symbolic.nblocks.into_usize();
+        unsafe {
+            Some(SymbolicOrdering {
+                dim,
+                entry_cnt: self.entry_cnt(),
+                pattern_hash: pattern_hash(self),
+                row_permutation: slice::from_raw_parts(symbolic.P, dim).to_vec(),
+                column_permutation: slice::from_raw_parts(symbolic.Q, dim).to_vec(),
+                block_boundaries: slice::from_raw_parts(symbolic.R, nblocks + 1).to_vec(),
+                block_lnz: slice::from_raw_parts(symbolic.Lnz, nblocks).to_vec(),
+                lnz: symbolic.lnz,
+                unz: symbolic.unz,
+                symmetry: symbolic.symmetry,
+                est_flops: symbolic.est_flops,
+                method: symbolic.ordering.into_usize() as u64,
+                do_btf: symbolic.do_btf != I::from_usize(0),
+            })
+        }
+    }
+
+    /// Constructs a new matrix spec from a column sparse matrix description and restores the
+    /// symbolic analysis from `ordering` (see [`export_ordering`](Self::export_ordering))
+    /// instead of computing it. The `btf` and `ordering` options of `klu_settings` are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::InvalidPattern`] if the pattern is invalid (see
+    /// [`new`](Self::new)), [`KluError::Invalid`] if `ordering` was computed for a different
+    /// pattern and any other error if KLU fails to construct the symbolic object.
+    pub fn with_ordering(
+        columns: &[Vec<I>],
+        ordering: &SymbolicOrdering<I>,
+        klu_settings: KluSettings<I>,
+    ) -> Result<Rc<Self>, KluError> {
+        let mut res = Self::empty(klu_settings);
+        res.set_pattern(columns)?;
+        res.restore_ordering(ordering)?;
+        Ok(Rc::new(res))
+    }
+
+    fn restore_ordering(&mut self, ordering: &SymbolicOrdering<I>) -> Result<(), KluError> {
+        if ordering.dim != self.dim()
+            || ordering.entry_cnt != self.entry_cnt()
+            || ordering.pattern_hash != pattern_hash(self)
+        {
+            return Err(KluError::Invalid);
+        }
+        let (nzoff, maxblock) = ordering.check_blocks(self).ok_or(KluError::Invalid)?;
+
+        // with BTF enabled klu_analyze_given recomputes the block triangular form which discards
+        // the ordering within the blocks. Instead a single block is analyzed and the blocks are
+        // restored afterwards.
+        let btf = self.settings.btf();
+        self.settings.set_options(|options| options.btf = false);
+        let res = self.analyze_pattern(Some(GivenPermutations {
+            rows: Some(&ordering.row_permutation),
+            columns: Some(&ordering.column_permutation),
+        }));
+        self.settings.set_options(|options| options.btf = btf);
+        res?;
+
+        let symbolic = unsafe { I::symbolic_fields_mut(self.symbolic_ptr()) };
+        let nblocks = ordering.nblocks();
+        // KLU allocates `n + 1` block boundaries and `n` estimates
+        unsafe {
+            slice::from_raw_parts_mut(symbolic.R, nblocks + 1)
+                .copy_from_slice(&ordering.block_boundaries);
+            slice::from_raw_parts_mut(symbolic.Lnz, nblocks).copy_from_slice(&ordering.block_lnz);
+        }
+        symbolic.nblocks = I::from_usize(nblocks);
+        symbolic.nzoff = I::from_usize(nzoff);
+        symbolic.maxblock = I::from_usize(maxblock);
+        symbolic.ordering = I::from_usize(ordering.method as usize);
+        symbolic.do_btf = I::from_usize(ordering.do_btf as usize);
+        symbolic.lnz = ordering.lnz;
+        symbolic.unz = ordering.unz;
+        symbolic.symmetry = ordering.symmetry;
+        symbolic.est_flops = ordering.est_flops;
+        Ok(())
+    }
+}
+
+/// FNV-1a hash of the pattern of `spec` that detects orderings restored for a different pattern
+fn pattern_hash<I: KluIndex>(spec: &KluMatrixSpec<I>) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325;
+    for idx in spec.column_offsets.iter().chain(spec.row_indices.iter()) {
+        for byte in (idx.into_usize() as u64).to_le_bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_f64(reader: &mut impl Read) -> io::Result<f64> {
+    read_u64(reader).map(f64::from_bits)
+}
+
+/// Reads a size that must be representable by the index type `I`
+fn read_size<I: KluIndex>(reader: &mut impl Read) -> io::Result<usize> {
+    let val = read_u64(reader)?;
+    match usize::try_from(val) {
+        Ok(val) if val <= I::MAX => Ok(val),
+        _ => Err(invalid_data(format!(
+            "{val} does not fit into the index type"
+        ))),
+    }
+}
+
+/// Reads `len` indices that must be smaller than `bound`
+fn read_indices<I: KluIndex>(
+    reader: &mut impl Read,
+    len: usize,
+    bound: usize,
+) -> io::Result<Vec<I>> {
+    (0..len)
+        .map(|_| {
+            let idx = read_u64(reader)?;
+            if idx >= bound as u64 {
+                return Err(invalid_data(format!("index {idx} is out of bounds")));
+            }
+            Ok(I::from_usize(idx as usize))
+        })
+        .collect()
+}
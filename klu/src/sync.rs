@@ -0,0 +1,43 @@
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::{FixedKluMatrix, KluData, KluIndex, KluMatrixSpec};
+
+mod sealed {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    use crate::{KluIndex, KluMatrixSpec};
+
+    pub trait Sealed {}
+    impl<I: KluIndex> Sealed for Rc<KluMatrixSpec<I>> {}
+    impl<I: KluIndex> Sealed for Arc<KluMatrixSpec<I>> {}
+}
+
+/// A shared pointer to a [`KluMatrixSpec`] that a [`FixedKluMatrix`] keeps alive.
+///
+/// This trait is implemented for `Rc<KluMatrixSpec<I>>` (the default) and
+/// `Arc<KluMatrixSpec<I>>`, which allows sharing a single symbolic analysis between threads.
+pub trait KluSpecRef<I: KluIndex>: Deref<Target = KluMatrixSpec<I>> + sealed::Sealed {}
+
+impl<I: KluIndex> KluSpecRef<I> for Rc<KluMatrixSpec<I>> {}
+
+impl<I: KluIndex> KluSpecRef<I> for Arc<KluMatrixSpec<I>> {}
+
+/// A matrix that can be moved to other threads. All matrices created from the same
+/// `Arc<KluMatrixSpec<I>>` share the symbolic analysis, while the numeric factorization,
+/// the matrix data and the KLU status are owned by each matrix.
+///
+/// The matrix is not `Sync` because its data is accessed through [`Cell`](std::cell::Cell).
+pub type SendKluMatrix<I, D> = FixedKluMatrix<I, D, Arc<KluMatrixSpec<I>>>;
+
+// SAFETY: The symbolic analysis, the pattern and the `KluCommon` of the spec are only modified
+// trough `&mut self`. Matrices never write to the `KluCommon` of the spec but use their own copy.
+// The user ordering is only invoked during analysis (trough `&mut self`) and is `Send`.
+unsafe impl<I: KluIndex> Send for KluMatrixSpec<I> {}
+unsafe impl<I: KluIndex> Sync for KluMatrixSpec<I> {}
+
+// SAFETY: The data and the numeric factorization are owned by the matrix and the KLU status
+// is written to the settings owned by the matrix. The shared spec is `Sync` and only read.
+unsafe impl<I: KluIndex, D: KluData> Send for SendKluMatrix<I, D> {}
@@ -1,5 +1,7 @@
 use std::collections::HashSet;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use float_cmp::{ApproxEq, F64Margin};
 use num_complex::Complex64;
@@ -8,7 +10,12 @@ use proptest::strategy::Just;
 use proptest::{collection, prop_assert, proptest};
 
 use crate::raw::KluData;
-use crate::{FixedKluMatrix, KluMatrixBuilder, KluMatrixSpec, KluSettings};
+use crate::{
+    read_matrix_market_vector, write_matrix_market_vector, FactorOutcome, Factorization,
+    FixedKluMatrix, FullFactorReason, KluError, KluMatrixBuilder, KluMatrixSpec, KluOrdering,
+    KluScaling, KluSettings, MatrixReadError, PatternError, RefactorPolicy, SettingError,
+    SymbolicOrdering, TripletAssembler,
+};
 
 proptest! {
     #[test]
@@ -34,6 +41,1376 @@ fn smoke_test() {
     .expect("smoke test failed")
 }
 
+#[test]
+fn invalid_pattern() {
+    let err = KluMatrixSpec::<i32>::new(&[vec![0], vec![2]], KluSettings::new()).unwrap_err();
+    assert_eq!(
+        err,
+        KluError::InvalidPattern(PatternError::EntryOutOfBounds {
+            column: 1,
+            row: 2,
+            dim: 2
+        })
+    );
+    assert_eq!(
+        err.to_string(),
+        "invalid sparsity pattern: entry at column 1, row 2 is outside of the 2x2 matrix"
+    );
+
+    let err = KluMatrixSpec::<i32>::new(&[vec![0, -1], vec![1, 0, 1]], KluSettings::new());
+    assert_eq!(
+        err.unwrap_err(),
+        KluError::InvalidPattern(PatternError::EntryOutOfBounds {
+            column: 0,
+            row: -1,
+            dim: 2
+        })
+    );
+    let err = KluMatrixSpec::<i32>::new(&[vec![0], vec![1, 0, 1]], KluSettings::new());
+    assert_eq!(
+        err.unwrap_err(),
+        KluError::InvalidPattern(PatternError::DuplicateEntry { column: 1, row: 1 })
+    );
+    let err = KluMatrixSpec::<i32>::new(&[], KluSettings::new()).unwrap_err();
+    assert_eq!(err, KluError::InvalidPattern(PatternError::Empty));
+
+    let mut builder = KluMatrixBuilder::new(2);
+    builder.add_entry(0, 0);
+    builder.add_entry(1, 3);
+    let err = builder.finish(KluSettings::new()).unwrap_err();
+    assert_eq!(
+        err,
+        KluError::InvalidPattern(PatternError::EntryOutOfBounds {
+            column: 1,
+            row: 3,
+            dim: 2
+        })
+    );
+}
+
+#[test]
+fn singular_matrix() {
+    let spec = KluMatrixSpec::<i32>::new(&[vec![0, 1], vec![0, 1]], KluSettings::new()).unwrap();
+    let mut matrix = spec.create_matrix::<f64>().unwrap();
+    matrix.write_all(1.0);
+    assert!(matches!(
+        matrix
+            .lu_factorize(RefactorPolicy::never())
+            .map(|lu| lu.outcome()),
+        Err(KluError::Singular { .. })
+    ));
+}
+
+#[test]
+fn singular_factorization() {
+    // zero pivots are only reported for singleton blocks if halt_if_singular is disabled
+    let settings = KluSettings::new().with_halt_if_singular(false);
+    let spec = KluMatrixSpec::<i32>::new(&[vec![0], vec![1], vec![2]], settings).unwrap();
+    let mut matrix = spec.create_matrix::<f64>().unwrap();
+    matrix.write_all(2.0);
+    matrix[(1, 1)].set(0.0);
+    let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+    assert_eq!(lu.outcome(), FactorOutcome::Singular { column: 1 });
+    assert!(lu.settings().is_singular());
+    // the zero pivot produces an infinite solution which yields a NaN backward error
+    let mut rhs = vec![4.0; 3];
+    let stats = lu.solve_refined(&mut rhs, 5, 0.0).unwrap();
+    assert_eq!(stats.steps, 0);
+    assert!(stats.backward_error.is_nan());
+    let mut rhs = vec![4.0; 3];
+    assert!(matches!(
+        lu.solve_refined_with_bound(&mut rhs, 5, 0.0),
+        Err(KluError::Singular { .. })
+    ));
+
+    // new values require a new factorization
+    matrix[(1, 1)].set(4.0);
+    let lu = matrix.lu_factorize(RefactorPolicy::always()).unwrap();
+    assert_eq!(lu.outcome(), FactorOutcome::Refactored);
+    let mut rhs = vec![4.0; 3];
+    lu.solve_linear_system(&mut rhs).unwrap();
+    assert_eq!(rhs, [2.0, 1.0, 2.0]);
+}
+
+#[test]
+fn settings() {
+    let settings = KluSettings::<i64>::new();
+    assert_eq!(settings.ordering(), KluOrdering::Amd);
+    assert_eq!(settings.scaling(), KluScaling::Max);
+    assert!(settings.btf());
+
+    let settings = settings
+        .with_tol(0.1)
+        .with_memgrow(2.0)
+        .with_initmem_amd(1.5)
+        .with_initmem(4.0)
+        .with_maxwork(100.0)
+        .with_btf(false)
+        .with_ordering(KluOrdering::Colamd)
+        .with_scaling(KluScaling::Sum)
+        .with_halt_if_singular(false);
+    assert_eq!(settings.tol(), 0.1);
+    assert_eq!(settings.memgrow(), 2.0);
+    assert_eq!(settings.initmem_amd(), 1.5);
+    assert_eq!(settings.initmem(), 4.0);
+    assert_eq!(settings.maxwork(), 100.0);
+    assert!(!settings.btf());
+    assert_eq!(settings.ordering(), KluOrdering::Colamd);
+    assert_eq!(settings.scaling(), KluScaling::Sum);
+    assert!(!settings.halt_if_singular());
+
+    let spec = KluMatrixSpec::new(&[vec![0i64, 1], vec![0, 1]], settings).unwrap();
+    let mut matrix = spec.create_matrix::<f64>().unwrap();
+    matrix.write_all(1.0);
+    // KLU does not report numerically singular matrices if halt_if_singular is disabled
+    assert_eq!(
+        matrix
+            .lu_factorize(RefactorPolicy::never())
+            .map(|lu| lu.outcome()),
+        Ok(FactorOutcome::FullFactor {
+            reason: FullFactorReason::Initial
+        })
+    );
+}
+
+#[test]
+fn fallible_settings() {
+    let err = KluSettings::<i32>::new().try_with_tol(1.5).unwrap_err();
+    assert_eq!(
+        err,
+        SettingError {
+            setting: "tol",
+            value: 1.5,
+            expected: "within (0, 1]",
+        }
+    );
+    assert_eq!(err.to_string(), "tol must be within (0, 1] but was 1.5");
+    assert!(KluSettings::<i32>::new().try_with_memgrow(0.5).is_err());
+    assert!(KluSettings::<i32>::new()
+        .try_with_maxwork(f64::NAN)
+        .is_err());
+    let settings = KluSettings::<i32>::new()
+        .try_with_initmem(2.0)
+        .and_then(|settings| settings.try_with_initmem_amd(3.0))
+        .unwrap();
+    assert_eq!(settings.initmem(), 2.0);
+    assert_eq!(settings.initmem_amd(), 3.0);
+
+    // KLU orderings and scalings without a dedicated variant are preserved
+    let settings = settings
+        .with_ordering(KluOrdering::Other(2))
+        .with_scaling(KluScaling::Other(5));
+    assert_eq!(settings.ordering(), KluOrdering::Other(2));
+    assert_eq!(settings.scaling(), KluScaling::Other(5));
+    let settings = settings.with_ordering(KluOrdering::Other(7));
+    let err = KluMatrixSpec::new(&tridiagonal_pattern(8), settings).unwrap_err();
+    assert_eq!(err, KluError::Invalid);
+
+    // codes that KLU can not store are rejected instead of being truncated
+    let too_large = i64::from(i32::MAX) + 1;
+    let err = KluSettings::<i32>::new()
+        .try_with_ordering(KluOrdering::Other(too_large))
+        .unwrap_err();
+    assert_eq!(err.setting, "ordering");
+    assert!(KluSettings::<i32>::new()
+        .try_with_scaling(KluScaling::Other(-2))
+        .is_err());
+    let settings = KluSettings::<i64>::new()
+        .try_with_ordering(KluOrdering::Other(too_large))
+        .unwrap();
+    assert_eq!(settings.ordering(), KluOrdering::Other(too_large));
+}
+
+fn tridiagonal_pattern(dim: i32) -> Vec<Vec<i32>> {
+    (0..dim)
+        .map(|col| (col.max(1) - 1..(col + 2).min(dim)).collect())
+        .collect()
+}
+
+#[test]
+fn user_ordering() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let calls_ = calls.clone();
+    let settings = KluSettings::new().with_btf(false).with_user_ordering(
+        move |_: &[i32], row_indices: &[i32], perm: &mut [i32]| {
+            calls_.fetch_add(1, Ordering::Relaxed);
+            // reverse ordering
+            let dim = perm.len();
+            for (i, dst) in perm.iter_mut().enumerate() {
+                *dst = (dim - 1 - i) as i32;
+            }
+            Some(row_indices.len())
+        },
+    );
+    assert_eq!(settings.ordering(), KluOrdering::User);
+    let spec = KluMatrixSpec::new(&tridiagonal_pattern(8), settings).unwrap();
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+    let mut matrix = spec.create_matrix::<f64>().unwrap();
+    for col in 0..8 {
+        matrix[(col, col)].set(4.0);
+    }
+    let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+    let mut rhs = vec![8.0; 8];
+    lu.solve_linear_system(&mut rhs).unwrap();
+    assert!(rhs.iter().all(|&x| (x - 2.0).abs() < 1e-12));
+}
+
+#[test]
+fn user_ordering_huge_estimate() {
+    // the estimate does not fit into i32 and is clamped instead of aborting inside the callback
+    let settings = KluSettings::new().with_btf(false).with_user_ordering(
+        |_: &[i32], _: &[i32], perm: &mut [i32]| {
+            for (i, dst) in perm.iter_mut().enumerate() {
+                *dst = i as i32;
+            }
+            Some(usize::MAX)
+        },
+    );
+    assert!(KluMatrixSpec::new(&tridiagonal_pattern(8), settings).is_ok());
+}
+
+#[test]
+fn user_ordering_invalid_permutation() {
+    let settings = KluSettings::new().with_btf(false).with_user_ordering(
+        |_: &[i32], _: &[i32], perm: &mut [i32]| {
+            perm.fill(0);
+            Some(1)
+        },
+    );
+    let err = KluMatrixSpec::new(&tridiagonal_pattern(8), settings).unwrap_err();
+    assert_eq!(err, KluError::Invalid);
+}
+
+#[test]
+#[should_panic(expected = "ordering failed")]
+fn user_ordering_panic() {
+    let settings = KluSettings::new().with_btf(false).with_user_ordering(
+        |_: &[i32], _: &[i32], _: &mut [i32]| -> Option<usize> { panic!("ordering failed") },
+    );
+    let _ = KluMatrixSpec::new(&tridiagonal_pattern(8), settings);
+}
+
+#[test]
+fn given_permutations() {
+    let reverse: Vec<i32> = (0..8).rev().collect();
+    let mut builder = KluMatrixBuilder::new(8);
+    for (col, rows) in tridiagonal_pattern(8).into_iter().enumerate() {
+        for row in rows {
+            builder.add_entry(col as i32, row);
+        }
+    }
+    let spec = builder
+        .finish_given(Some(&reverse), Some(&reverse), KluSettings::new())
+        .unwrap();
+    let mut matrix = spec.create_matrix::<f64>().unwrap();
+    for col in 0..8 {
+        matrix[(col, col)].set(4.0);
+    }
+    let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+    let mut rhs = vec![8.0; 8];
+    lu.solve_linear_system(&mut rhs).unwrap();
+    assert!(rhs.iter().all(|&x| (x - 2.0).abs() < 1e-12));
+
+    let err = KluMatrixSpec::with_permutations(
+        &tridiagonal_pattern(8),
+        None,
+        Some(&[0, 1, 2, 3, 4, 5, 6, 6]),
+        KluSettings::new(),
+    )
+    .unwrap_err();
+    assert_eq!(err, KluError::Invalid);
+}
+
+#[test]
+fn multiple_rhs() {
+    let spec = KluMatrixSpec::new(&tridiagonal_pattern(6), KluSettings::new()).unwrap();
+    let mut matrix = spec.create_matrix::<Complex64>().unwrap();
+    for (i, entry) in matrix.data().iter().enumerate() {
+        entry.set(Complex64::new(i as f64 + 1.0, 0.5 * i as f64));
+    }
+    for col in 0..6 {
+        matrix[(col, col)].set(Complex64::new(10.0, 1.0));
+    }
+    let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+
+    let rhs: Vec<_> = (0..6 * 5)
+        .map(|i| Complex64::new(i as f64, 1.0 - i as f64))
+        .collect();
+    let mut solution = rhs.clone();
+    lu.solve_multiple(&mut solution, 5).unwrap();
+    let mut transpose_solution = rhs.clone();
+    lu.solve_multiple_transpose(&mut transpose_solution, 5)
+        .unwrap();
+    for i in 0..5 {
+        let mut single = rhs[i * 6..(i + 1) * 6].to_vec();
+        lu.solve_linear_system(&mut single).unwrap();
+        assert_eq!(single, solution[i * 6..(i + 1) * 6]);
+
+        let mut single = rhs[i * 6..(i + 1) * 6].to_vec();
+        lu.solve_linear_tranose_system(&mut single).unwrap();
+        assert_eq!(single, transpose_solution[i * 6..(i + 1) * 6]);
+    }
+
+    assert_eq!(
+        lu.solve_multiple(&mut solution[1..], 5),
+        Err(KluError::Invalid)
+    );
+    // `6 * nrhs` overflows to the length of `solution`
+    assert_eq!(
+        lu.solve_multiple(&mut solution, 5 + (1 << (usize::BITS - 1))),
+        Err(KluError::Invalid)
+    );
+}
+
+#[test]
+fn conjugate_transpose_solve() {
+    let pattern = tridiagonal_pattern(6);
+    let spec = KluMatrixSpec::new(&pattern, KluSettings::new()).unwrap();
+    let mut matrix = spec.create_matrix::<Complex64>().unwrap();
+    for (i, entry) in matrix.data().iter().enumerate() {
+        entry.set(Complex64::new(i as f64 + 1.0, 0.5 * i as f64 - 2.0));
+    }
+    for col in 0..6 {
+        matrix[(col, col)].set(Complex64::new(10.0, 3.0));
+    }
+    let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+
+    let rhs: Vec<_> = (0..6).map(|i| Complex64::new(i as f64, 1.0)).collect();
+    let mut solution = rhs.clone();
+    lu.solve_conjugate_transpose_system(&mut solution).unwrap();
+    let mut multiple = rhs.clone();
+    lu.solve_multiple_conjugate_transpose(&mut multiple, 1)
+        .unwrap();
+    assert_eq!(multiple, solution);
+
+    // check A^H x = b
+    let mut check = [Complex64::new(0.0, 0.0); 6];
+    for (col, rows) in pattern.iter().enumerate() {
+        for &row in rows {
+            check[col] += matrix[(col as i32, row)].get().conj() * solution[row as usize];
+        }
+    }
+    for (check, rhs) in check.iter().zip(&rhs) {
+        assert!((check - rhs).norm() < 1e-12, "{check} != {rhs}");
+    }
+}
+
+#[test]
+fn diagnostics() {
+    let spec = KluMatrixSpec::new(&tridiagonal_pattern(8), KluSettings::new()).unwrap();
+    let mut matrix = spec.create_matrix::<f64>().unwrap();
+    for col in 0..8 {
+        matrix[(col, col)].set(1.0);
+    }
+    let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+    // the identity matrix is perfectly conditioned
+    assert!((lu.condest().unwrap() - 1.0).abs() < 1e-12);
+    assert!((lu.reciprocal_pivot_growth().unwrap() - 1.0).abs() < 1e-12);
+    assert!(lu.flops().unwrap() >= 0.0);
+
+    matrix[(0, 0)].set(1e-10);
+    let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+    assert!(lu.condest().unwrap() >= 1e10);
+}
+
+#[test]
+fn iterative_refinement() {
+    let pattern = tridiagonal_pattern(8);
+    let spec = KluMatrixSpec::new(&pattern, KluSettings::new()).unwrap();
+    let exact: Vec<_> = (0..8).map(|i| f64::from(i) - 3.5).collect();
+    // a well conditioned and a badly (column) scaled tridiagonal matrix
+    for column_scale in [1f64, 100.0] {
+        let mut matrix = spec.clone().create_matrix::<f64>().unwrap();
+        for (col, rows) in pattern.iter().enumerate() {
+            for &row in rows {
+                let val = if row == col as i32 { 3.0 } else { -1.0 };
+                matrix[(col as i32, row)].set(val * column_scale.powi(col as i32 - 4));
+            }
+        }
+        let mut rhs = vec![0.0; 8];
+        matrix.mul_vec(&exact, &mut rhs).unwrap();
+
+        let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+        let mut solution = rhs.clone();
+        let stats = lu.solve_refined_with_bound(&mut solution, 5, 0.0).unwrap();
+        assert!(stats.steps <= 5);
+        assert!(stats.backward_error < 1e-15, "{stats:?}");
+        let error: f64 = solution
+            .iter()
+            .zip(&exact)
+            .map(|(x, y)| (x - y).abs())
+            .sum();
+        let norm: f64 = solution.iter().map(|x| x.abs()).sum();
+        if let Some(bound) = stats.forward_error_bound {
+            assert!(error / norm <= bound, "{stats:?}");
+        }
+        // the well conditioned matrix always gets a bound
+        assert!(column_scale != 1.0 || stats.forward_error_bound.is_some());
+
+        // without refinement steps the plain solution is returned
+        let mut unrefined = rhs.clone();
+        let stats = lu.solve_refined(&mut unrefined, 0, 0.0).unwrap();
+        assert_eq!(stats.steps, 0);
+        assert_eq!(stats.forward_error_bound, None);
+        let mut plain = rhs.clone();
+        lu.solve_linear_system(&mut plain).unwrap();
+        assert_eq!(unrefined, plain);
+
+        assert_eq!(
+            lu.solve_refined(&mut rhs[1..], 5, 0.0),
+            Err(KluError::Invalid)
+        );
+
+        // the refined solution reproduces the right hand side
+        let mut product = vec![0.0; 8];
+        matrix.mul_vec(&solution, &mut product).unwrap();
+        for (product, rhs) in product.iter().zip(&rhs) {
+            assert!((product - rhs).abs() <= 1e-12 * rhs.abs().max(1.0));
+        }
+    }
+}
+
+#[test]
+fn iterative_refinement_steps() {
+    // the tiny pivot tolerance accepts the tiny diagonal entries as pivots which makes the
+    // initial solution inaccurate
+    let pattern = tridiagonal_pattern(8);
+    let settings = || {
+        KluSettings::new()
+            .with_tol(1e-300)
+            .with_scaling(KluScaling::None)
+    };
+    let value = |row: i32, col: i32| {
+        if row == col {
+            1e-10
+        } else {
+            1.0 + f64::from(row)
+        }
+    };
+    let csc = KluMatrixSpec::new(&pattern, settings()).unwrap();
+    // the pattern is symmetric so its columns also describe the rows
+    let row_offsets = pattern.iter().scan(0, |offset, rows| {
+        *offset += rows.len() as i32;
+        Some(*offset)
+    });
+    let row_offsets = std::iter::once(0).chain(row_offsets).collect();
+    let csr = KluMatrixSpec::from_csr(8, row_offsets, pattern.concat(), settings()).unwrap();
+    for spec in [csc, csr] {
+        check_refinement(spec.clone(), &pattern, value);
+        check_refinement(spec, &pattern, |row, col| {
+            Complex64::new(value(row, col), f64::from(row - col))
+        });
+    }
+}
+
+/// Solves a system with the values produced by `value(row, column)` and checks that iterative
+/// refinement improves the inaccurate initial solution
+fn check_refinement<D: KluData>(
+    spec: Rc<KluMatrixSpec<i32>>,
+    pattern: &[Vec<i32>],
+    value: impl Fn(i32, i32) -> D,
+) {
+    let mut matrix = spec.create_matrix::<D>().unwrap();
+    for (col, rows) in pattern.iter().enumerate() {
+        for &row in rows {
+            matrix[(col as i32, row)].set(value(row, col as i32));
+        }
+    }
+    let exact: Vec<_> = (0..8)
+        .map(|i| D::from_parts(f64::from(i) - 3.5, 1.0))
+        .collect();
+    let mut rhs = vec![D::zero(); 8];
+    matrix.mul_vec(&exact, &mut rhs).unwrap();
+
+    let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+    let mut plain = rhs.clone();
+    let initial = lu.solve_refined(&mut plain, 0, 0.0).unwrap();
+    let mut refined = rhs.clone();
+    let stats = lu.solve_refined(&mut refined, 10, 1e-15).unwrap();
+    assert!(initial.backward_error > 1e-10, "{initial:?}");
+    assert!(stats.steps > 0);
+    assert!(stats.backward_error <= 1e-15, "{stats:?}");
+    let error = |x: &[D]| -> f64 { x.iter().zip(&exact).map(|(x, y)| (*x - *y).abs()).sum() };
+    assert!(error(&refined) < 1e-3 * error(&plain));
+}
+
+#[test]
+fn extract_factors() {
+    check_extracted_factors(|row, col| (1 + row + 2 * col) as f64);
+}
+
+#[test]
+fn extract_complex_factors() {
+    // the real and imaginary parts are extracted into separate arrays by KLU
+    check_extracted_factors(|row, col| {
+        Complex64::new((1 + row + 2 * col) as f64, (row - col) as f64)
+    });
+}
+
+/// Factorizes a matrix with the values produced by `value(row, column)`, checks the factor
+/// statistics and rebuilds the scaled and permuted matrix from `L*U + F`
+fn check_extracted_factors<D: KluData>(value: impl Fn(i32, i32) -> D) {
+    // the first two columns are coupled, the rest of the matrix is upper bidiagonal so the
+    // factorization contains multiple blocks and off-diagonal entries
+    let columns = vec![vec![0, 1], vec![0, 1], vec![1, 2], vec![2, 3], vec![3, 4]];
+    let spec = KluMatrixSpec::new(&columns, KluSettings::new()).unwrap();
+    let mut matrix = spec.create_matrix::<D>().unwrap();
+    let mut dense = [[D::zero(); 5]; 5];
+    for (col, rows) in columns.iter().enumerate() {
+        for &row in rows {
+            let val = value(row, col as i32);
+            matrix[(col as i32, row)].set(val);
+            dense[row as usize][col] = val;
+        }
+    }
+    let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+    let factors = lu.extract_factors().unwrap();
+    assert!(factors.block_boundaries.len() > 2);
+    assert_eq!(factors.block_boundaries.last(), Some(&5));
+
+    let stats = lu.factor_stats();
+    assert_eq!(stats.nblocks, factors.block_boundaries.len() - 1);
+    assert_eq!(stats.lnz, factors.l.values.len());
+    assert_eq!(stats.unz, factors.u.values.len());
+    assert_eq!(stats.nzoff, factors.f.values.len());
+    assert!(stats.max_lnz_block <= stats.lnz && stats.max_unz_block <= stats.unz);
+
+    // L*U + F stored column by column
+    let mut lu = [[D::zero(); 5]; 5];
+    for (col, lu_col) in lu.iter_mut().enumerate() {
+        let f = &factors.f;
+        for k in f.column_offsets[col]..f.column_offsets[col + 1] {
+            lu_col[f.row_indices[k as usize] as usize] += f.values[k as usize];
+        }
+        let u = &factors.u;
+        for k in u.column_offsets[col]..u.column_offsets[col + 1] {
+            let (mid, u_val) = (u.row_indices[k as usize] as usize, u.values[k as usize]);
+            let l = &factors.l;
+            for j in l.column_offsets[mid]..l.column_offsets[mid + 1] {
+                lu_col[l.row_indices[j as usize] as usize] += l.values[j as usize] * u_val;
+            }
+        }
+    }
+
+    for (j, lu_col) in lu.iter().enumerate() {
+        for (i, &val) in lu_col.iter().enumerate() {
+            let row = factors.row_permutation[i] as usize;
+            let col = factors.column_permutation[j] as usize;
+            let expected = dense[row][col] / D::from_parts(factors.row_scaling[row], 0.0);
+            assert!((val - expected).abs() < 1e-12, "{val:?} != {expected:?}");
+        }
+    }
+}
+
+#[test]
+fn shared_spec() {
+    let spec = KluMatrixSpec::new_shared(&tridiagonal_pattern(8), KluSettings::new()).unwrap();
+    let threads: Vec<_> = (0..4)
+        .map(|i| {
+            let spec = spec.clone();
+            std::thread::spawn(move || {
+                let mut matrix = spec.create_send_matrix::<f64>().unwrap();
+                matrix.write_all(-1.0);
+                for col in 0..8 {
+                    matrix[(col, col)].set(4.0 + i as f64);
+                }
+                // thread 0 factorizes a singular matrix which must not affect the other threads
+                if i == 0 {
+                    matrix.write_zero();
+                }
+                let res = matrix.lu_factorize(RefactorPolicy::never());
+                if i == 0 {
+                    assert!(matches!(res, Err(KluError::Singular { .. })));
+                    assert!(matrix.settings().is_singular());
+                    return None;
+                }
+                let lu = res.unwrap();
+                assert!(!lu.settings().is_singular());
+                let mut rhs = vec![1.0; 8];
+                lu.solve_linear_system(&mut rhs).unwrap();
+                Some(rhs)
+            })
+        })
+        .collect();
+
+    for (i, thread) in threads.into_iter().enumerate() {
+        let solution = thread.join().unwrap();
+        assert_eq!(solution.is_none(), i == 0);
+    }
+}
+
+#[test]
+fn per_matrix_status() {
+    let spec = KluMatrixSpec::new(&tridiagonal_pattern(4), KluSettings::new()).unwrap();
+    let mut singular = spec.clone().create_matrix::<f64>().unwrap();
+    let mut regular = spec.create_matrix::<f64>().unwrap();
+    for col in 0..4 {
+        regular[(col, col)].set(2.0);
+    }
+    regular.lu_factorize(RefactorPolicy::never()).unwrap();
+    let outcome = regular
+        .lu_factorize(RefactorPolicy::always().with_min_rcond(1e-3))
+        .map(|lu| lu.outcome());
+    assert_eq!(outcome, Ok(FactorOutcome::Refactored));
+    assert!(matches!(
+        singular
+            .lu_factorize(RefactorPolicy::never())
+            .map(|lu| lu.outcome()),
+        Err(KluError::Singular { .. })
+    ));
+
+    assert!(singular.settings().is_singular());
+    assert!(!regular.settings().is_singular());
+    assert!((regular.settings().get_rcond() - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn refactor_policy() {
+    let full = |reason| Ok(FactorOutcome::FullFactor { reason });
+    let spec = KluMatrixSpec::new(&tridiagonal_pattern(4), KluSettings::new()).unwrap();
+    let mut matrix = spec.create_matrix::<f64>().unwrap();
+    matrix.write_all(1.0);
+    for col in 0..4 {
+        matrix[(col, col)].set(4.0);
+    }
+
+    let policy = RefactorPolicy::always().with_full_factor_every(3);
+    let mut factorize = |policy| matrix.lu_factorize(policy).map(|lu| lu.outcome());
+    assert_eq!(factorize(policy), full(FullFactorReason::Initial));
+    assert_eq!(factorize(policy), Ok(FactorOutcome::Refactored));
+    assert_eq!(factorize(policy), Ok(FactorOutcome::Refactored));
+    assert_eq!(factorize(policy), full(FullFactorReason::Scheduled));
+    assert_eq!(
+        factorize(RefactorPolicy::never()),
+        full(FullFactorReason::Disabled)
+    );
+
+    // a tiny pivot makes the (cheap) condition estimate small
+    matrix[(3, 3)].set(1e-10);
+    let outcome = matrix
+        .lu_factorize(RefactorPolicy::always().with_min_rcond(1e-3))
+        .map(|lu| lu.outcome());
+    assert!(matches!(
+        outcome,
+        Ok(FactorOutcome::FullFactor {
+            reason: FullFactorReason::LowRcond { rcond }
+        }) if rcond < 1e-3
+    ));
+
+    // reusing the pivots of a diagonally dominant matrix leads to a large pivot growth
+    let spec = KluMatrixSpec::new(&tridiagonal_pattern(2), KluSettings::new()).unwrap();
+    let mut matrix = spec.create_matrix::<f64>().unwrap();
+    matrix.write_all(1.0);
+    matrix[(0, 0)].set(4.0);
+    matrix[(1, 1)].set(4.0);
+    matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+    matrix[(0, 0)].set(1e-6);
+    matrix[(1, 1)].set(1e-6);
+    let lu = matrix
+        .lu_factorize(RefactorPolicy::always().with_max_pivot_growth(1e3))
+        .unwrap();
+    assert!(matches!(
+        lu.outcome(),
+        FactorOutcome::FullFactor {
+            reason: FullFactorReason::PivotGrowth { growth }
+        } if growth > 1e3
+    ));
+    let mut rhs = vec![1.0; 2];
+    lu.solve_linear_system(&mut rhs).unwrap();
+    assert!(rhs.iter().all(|&x| (x - 1.0 / (1.0 + 1e-6)).abs() < 1e-9));
+}
+
+#[test]
+fn triplet_assembly() {
+    let mut assembler = TripletAssembler::<i32, f64>::new(3, KluSettings::new());
+    let triplets = [
+        (0, 0, 2.0),
+        (1, 1, 3.0),
+        (2, 2, 4.0),
+        (0, 0, 1.0),
+        (2, 0, 1.0),
+    ];
+    let matrix = assembler.assemble(&triplets).unwrap();
+    assert_eq!(matrix.data().len(), 4);
+    assert_eq!(matrix[(0, 0)].get(), 3.0);
+    assert_eq!(matrix[(0, 2)].get(), 1.0);
+    let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+    let mut rhs = [3.0, 3.0, 5.0];
+    lu.solve_linear_system(&mut rhs).unwrap();
+    assert_eq!(rhs, [1.0, 1.0, 1.0]);
+
+    // same pattern: values are scattered into the existing matrix
+    let data_ptr = assembler.matrix().unwrap().data_ptr();
+    let triplets = triplets.map(|(row, column, val)| (row, column, 2.0 * val));
+    let matrix = assembler.assemble(&triplets).unwrap();
+    assert_eq!(matrix.data_ptr(), data_ptr);
+    assert_eq!(matrix[(0, 0)].get(), 6.0);
+
+    // new pattern: the matrix is analyzed again
+    let matrix = assembler
+        .assemble(&[(0, 0, 1.0), (1, 1, 1.0), (2, 2, 1.0), (1, 2, 5.0)])
+        .unwrap();
+    assert_eq!(matrix[(2, 1)].get(), 5.0);
+    assert!(matrix.get(0, 2).is_none());
+
+    assert_eq!(
+        assembler.assemble(&[(3, 0, 1.0)]).err(),
+        Some(KluError::InvalidPattern(PatternError::EntryOutOfBounds {
+            column: 0,
+            row: 3,
+            dim: 3
+        }))
+    );
+}
+
+#[test]
+fn triplet_assembly_with_shared_spec() {
+    let mut assembler = TripletAssembler::<i32, f64>::new(2, KluSettings::new());
+    let matrix = assembler.assemble(&[(0, 0, 2.0), (1, 1, 4.0)]).unwrap();
+    let old_spec = matrix.spec().clone();
+
+    // the previous spec is still alive so a new one is analyzed
+    let matrix = assembler
+        .assemble(&[(0, 0, 2.0), (1, 1, 4.0), (0, 1, 1.0)])
+        .unwrap();
+    assert!(!Rc::ptr_eq(matrix.spec(), &old_spec));
+    assert_eq!(matrix[(1, 0)].get(), 1.0);
+    let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+    let mut rhs = [3.0, 4.0];
+    lu.solve_linear_system(&mut rhs).unwrap();
+    assert_eq!(rhs, [1.0, 1.0]);
+
+    // the clone keeps the old pattern
+    assert_eq!(old_spec.offset(1, 1), Some(1));
+    assert!(old_spec.offset(1, 0).is_none());
+}
+
+#[test]
+fn triplet_assembly_with_user_ordering() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let calls_ = calls.clone();
+    let settings = KluSettings::new().with_btf(false).with_user_ordering(
+        move |_: &[i32], row_indices: &[i32], perm: &mut [i32]| {
+            calls_.fetch_add(1, Ordering::Relaxed);
+            for (i, dst) in perm.iter_mut().enumerate() {
+                *dst = i as i32;
+            }
+            Some(row_indices.len())
+        },
+    );
+    let mut assembler = TripletAssembler::<i32, f64>::new(5, settings);
+    let diagonal: Vec<_> = (0..5).map(|i| (i, i, 4.0)).collect();
+    let coupling = |offset: i32| (0..5 - offset).map(move |i| (i + offset, i, -1.0));
+    let lower: Vec<_> = diagonal.iter().copied().chain(coupling(1)).collect();
+    let matrix = assembler.assemble(&lower).unwrap();
+    let old_spec = matrix.spec().clone();
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+    // the new spec shares the user ordering of the spec that is still alive
+    let both: Vec<_> = lower
+        .iter()
+        .copied()
+        .chain(coupling(1).map(|(row, col, val)| (col, row, val)))
+        .collect();
+    let matrix = assembler.assemble(&both).unwrap();
+    assert!(!Rc::ptr_eq(matrix.spec(), &old_spec));
+    assert_eq!(matrix.spec().settings.ordering(), KluOrdering::User);
+    assert_eq!(calls.load(Ordering::Relaxed), 2);
+    let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+    let mut rhs = [3.0, 2.0, 2.0, 2.0, 3.0];
+    lu.solve_linear_system(&mut rhs).unwrap();
+    assert!(rhs.iter().all(|&x| (x - 1.0).abs() < 1e-12));
+}
+
+#[test]
+fn entry_handles() {
+    let spec = KluMatrixSpec::new(&tridiagonal_pattern(4), KluSettings::new()).unwrap();
+    let other_spec = KluMatrixSpec::new(&tridiagonal_pattern(4), KluSettings::new()).unwrap();
+    let diagonal: Vec<_> = (0..4).map(|col| spec.resolve(col, col).unwrap()).collect();
+    assert!(spec.resolve(0, 3).is_none());
+
+    let matrix = spec.create_matrix::<f64>().unwrap();
+    for _ in 0..3 {
+        for &handle in &diagonal {
+            matrix.add_at(handle, 1.5).unwrap();
+        }
+    }
+    for col in 0..4 {
+        assert_eq!(matrix[(col, col)].get(), 4.5);
+        assert_eq!(matrix.get_at(diagonal[col as usize]).unwrap().get(), 4.5);
+    }
+
+    let other_matrix = other_spec.create_matrix::<f64>().unwrap();
+    assert_eq!(
+        other_matrix.add_at(diagonal[0], 1.0),
+        Err(KluError::Invalid)
+    );
+    assert!(other_matrix.get_at(diagonal[0]).is_none());
+    assert_eq!(other_matrix[(0, 0)].get(), 0.0);
+}
+
+#[test]
+fn unsorted_columns() {
+    let columns = vec![vec![2, 0, 1], vec![1], vec![2, 0]];
+    let spec = KluMatrixSpec::new(&columns, KluSettings::new()).unwrap();
+    for (col, rows) in columns.iter().enumerate() {
+        for &row in rows {
+            let offset = spec.offset(col as i32, row).unwrap();
+            assert_eq!(spec.row_indices[offset], row);
+        }
+    }
+    assert_eq!(spec.offset(1, 0), None);
+    assert_eq!(&*spec.row_indices, &[0, 1, 2, 1, 0, 2]);
+}
+
+#[test]
+fn from_csc() {
+    let spec =
+        KluMatrixSpec::from_csc(3, vec![0, 2, 3, 5], vec![1, 0, 1, 2, 0], KluSettings::new())
+            .unwrap();
+    assert_eq!(&*spec.row_indices, &[0, 1, 1, 0, 2]);
+    let matrix = spec.create_matrix::<f64>().unwrap();
+    assert!(matrix.get(2, 0).is_some());
+    assert!(matrix.get(1, 0).is_none());
+
+    let invalid = [
+        // offsets do not cover all rows
+        (
+            vec![0, 2, 3, 4],
+            vec![1, 0, 1, 2, 0],
+            PatternError::InvalidOffset { index: 3 },
+        ),
+        // decreasing offsets
+        (
+            vec![0, 3, 2, 5],
+            vec![1, 0, 1, 2, 0],
+            PatternError::InvalidOffset { index: 2 },
+        ),
+        // row out of bounds
+        (
+            vec![0, 2, 3, 5],
+            vec![1, 0, 3, 2, 0],
+            PatternError::EntryOutOfBounds {
+                column: 1,
+                row: 3,
+                dim: 3,
+            },
+        ),
+        // duplicate row
+        (
+            vec![0, 2, 3, 5],
+            vec![1, 1, 1, 2, 0],
+            PatternError::DuplicateEntry { column: 0, row: 1 },
+        ),
+        // wrong number of columns
+        (
+            vec![0, 2, 5],
+            vec![1, 0, 1, 2, 0],
+            PatternError::OffsetCount {
+                expected: 4,
+                found: 3,
+            },
+        ),
+    ];
+    for (column_offsets, row_indices, err) in invalid {
+        let res = KluMatrixSpec::from_csc(3, column_offsets, row_indices, KluSettings::new());
+        assert_eq!(res.err(), Some(KluError::InvalidPattern(err)));
+    }
+
+    // positions are reported in terms of the matrix for CSR arrays
+    let res = KluMatrixSpec::from_csr(3, vec![0, 2, 3, 5], vec![1, 0, 3, 2, 0], KluSettings::new());
+    assert_eq!(
+        res.err(),
+        Some(KluError::InvalidPattern(PatternError::EntryOutOfBounds {
+            column: 3,
+            row: 1,
+            dim: 3
+        }))
+    );
+}
+
+#[test]
+fn from_csr() {
+    // A = [[4, 1, 0], [0, 3, 2i], [1, 0, 5]]
+    let row_offsets = vec![0, 2, 4, 6];
+    let column_indices = vec![0, 1, 1, 2, 0, 2];
+    let values = [
+        Complex64::new(4.0, 0.0),
+        Complex64::new(1.0, 0.0),
+        Complex64::new(3.0, 0.0),
+        Complex64::new(0.0, 2.0),
+        Complex64::new(1.0, 0.0),
+        Complex64::new(5.0, 0.0),
+    ];
+    let csr = KluMatrixSpec::from_csr(
+        3,
+        row_offsets.clone(),
+        column_indices.clone(),
+        KluSettings::new(),
+    )
+    .unwrap();
+    assert!(csr.is_transposed());
+    let mut csr_matrix = csr.clone().create_matrix::<Complex64>().unwrap();
+    let mut csc_builder = KluMatrixBuilder::new(3);
+    for row in 0..3 {
+        for k in row_offsets[row]..row_offsets[row + 1] {
+            csc_builder.add_entry(column_indices[k as usize], row as i32);
+        }
+    }
+    let mut csc_matrix = csc_builder
+        .finish(KluSettings::new())
+        .unwrap()
+        .create_matrix::<Complex64>()
+        .unwrap();
+    for row in 0..3 {
+        for k in row_offsets[row]..row_offsets[row + 1] {
+            let column = column_indices[k as usize];
+            // the data of the CSR spec is stored in row major order
+            assert_eq!(csr.offset(column, row as i32), Some(k as usize));
+            csr_matrix[(column, row as i32)].set(values[k as usize]);
+            csc_matrix[(column, row as i32)].set(values[k as usize]);
+        }
+    }
+    let csr_lu = csr_matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+    let csc_lu = csc_matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+
+    let rhs = [
+        Complex64::new(1.0, 2.0),
+        Complex64::new(-1.0, 0.5),
+        Complex64::new(3.0, -1.0),
+    ];
+    type Solve = fn(&Factorization<i32, Complex64>, &mut [Complex64]) -> Result<(), KluError>;
+    let solves: [Solve; 3] = [
+        |lu, rhs| lu.solve_linear_system(rhs),
+        |lu, rhs| lu.solve_linear_tranose_system(rhs),
+        |lu, rhs| lu.solve_conjugate_transpose_system(rhs),
+    ];
+    for solve in solves {
+        let mut csr_solution = rhs;
+        solve(&csr_lu, &mut csr_solution).unwrap();
+        let mut csc_solution = rhs;
+        solve(&csc_lu, &mut csc_solution).unwrap();
+        for (csr, csc) in csr_solution.iter().zip(&csc_solution) {
+            assert!((csr - csc).norm() < 1e-12, "{csr} != {csc}");
+        }
+    }
+}
+
+#[test]
+fn matrix_vector_products() {
+    // A = [[4, 1, 0], [0, 3, 2i], [1, 0, 5]]
+    let c = |re, im| Complex64::new(re, im);
+    let dense = [
+        [c(4.0, 0.0), c(1.0, 0.0), c(0.0, 0.0)],
+        [c(0.0, 0.0), c(3.0, 0.0), c(0.0, 2.0)],
+        [c(1.0, 0.0), c(0.0, 0.0), c(5.0, 0.0)],
+    ];
+    let csc = KluMatrixSpec::from_csc(
+        3,
+        vec![0, 2, 4, 6],
+        vec![0, 2, 0, 1, 1, 2],
+        KluSettings::new(),
+    )
+    .unwrap();
+    let csr = KluMatrixSpec::from_csr(
+        3,
+        vec![0, 2, 4, 6],
+        vec![0, 1, 1, 2, 0, 2],
+        KluSettings::new(),
+    )
+    .unwrap();
+
+    let x = [c(1.0, 2.0), c(-1.0, 0.5), c(3.0, -1.0)];
+    for spec in [csc, csr] {
+        let matrix = spec.create_matrix::<Complex64>().unwrap();
+        for (row, values) in dense.iter().enumerate() {
+            for (column, &val) in values.iter().enumerate() {
+                if let Some(entry) = matrix.get(column as i32, row as i32) {
+                    entry.set(val);
+                }
+            }
+        }
+
+        let mut expected = [[c(0.0, 0.0); 3]; 3];
+        for i in 0..3 {
+            for j in 0..3 {
+                expected[0][i] += dense[i][j] * x[j];
+                expected[1][i] += dense[j][i] * x[j];
+                expected[2][i] += dense[j][i].conj() * x[j];
+            }
+        }
+        type Mul = fn(
+            &FixedKluMatrix<i32, Complex64>,
+            &[Complex64],
+            &mut [Complex64],
+        ) -> Result<(), KluError>;
+        let products: [(Mul, Mul); 3] = [
+            (FixedKluMatrix::mul_vec, FixedKluMatrix::gaxpy),
+            (
+                FixedKluMatrix::mul_vec_transposed,
+                FixedKluMatrix::gaxpy_transposed,
+            ),
+            (
+                FixedKluMatrix::mul_vec_conjugate_transposed,
+                FixedKluMatrix::gaxpy_conjugate_transposed,
+            ),
+        ];
+        for ((mul, gaxpy), expected) in products.into_iter().zip(expected) {
+            let mut y = [c(7.0, 7.0); 3];
+            mul(&matrix, &x, &mut y).unwrap();
+            assert_eq!(y, expected);
+            gaxpy(&matrix, &x, &mut y).unwrap();
+            assert_eq!(y, expected.map(|val| val * 2.0));
+            // the output is left untouched if the dimensions do not match
+            assert_eq!(mul(&matrix, &x[1..], &mut y), Err(KluError::Invalid));
+            assert_eq!(y, expected.map(|val| val * 2.0));
+            assert_eq!(mul(&matrix, &x, &mut y[1..]), Err(KluError::Invalid));
+            assert_eq!(y, expected.map(|val| val * 2.0));
+        }
+
+        assert_eq!(matrix.norm_one(), 7.0);
+        assert_eq!(matrix.norm_inf(), 6.0);
+    }
+}
+
+#[test]
+fn matrix_market() {
+    let file = "%%MatrixMarket matrix coordinate complex general
+% a comment
+3 3 5
+
+1 1 4.5 1
+2 1 -1 0
+3 2 2e-3 -0.5
+2 3 1 1
+2 3 1 1
+";
+    let matrix =
+        FixedKluMatrix::<i32, Complex64>::read_matrix_market(file.as_bytes(), KluSettings::new())
+            .unwrap();
+    assert_eq!(matrix.spec().entry_cnt(), 4);
+    assert_eq!(matrix[(0, 0)].get(), Complex64::new(4.5, 1.0));
+    assert_eq!(matrix[(2, 1)].get(), Complex64::new(2.0, 2.0));
+
+    let mut exported = Vec::new();
+    matrix.write_matrix_market(&mut exported).unwrap();
+    let reimported =
+        FixedKluMatrix::<i32, Complex64>::read_matrix_market(&*exported, KluSettings::new())
+            .unwrap();
+    assert_eq!(&*reimported.spec().row_indices, &*matrix.spec().row_indices);
+    for (a, b) in matrix.data().iter().zip(reimported.data()) {
+        assert_eq!(a.get(), b.get());
+    }
+
+    let mut pattern = Vec::new();
+    matrix.spec().write_matrix_market(&mut pattern).unwrap();
+    let pattern =
+        FixedKluMatrix::<i32, f64>::read_matrix_market(&*pattern, KluSettings::new()).unwrap();
+    assert_eq!(&*pattern.spec().row_indices, &*matrix.spec().row_indices);
+
+    let rhs = [0.1, -3.0, 1e300];
+    let mut exported = Vec::new();
+    write_matrix_market_vector(&rhs, &mut exported).unwrap();
+    assert_eq!(read_matrix_market_vector::<f64>(&*exported).unwrap(), rhs);
+
+    let err = FixedKluMatrix::<i32, f64>::read_matrix_market(file.as_bytes(), KluSettings::new());
+    assert!(matches!(err, Err(MatrixReadError::Unsupported(_))));
+    let file = "%%MatrixMarket matrix coordinate real general\n2 2 1\n3 1 1.0\n";
+    let err = FixedKluMatrix::<i32, f64>::read_matrix_market(file.as_bytes(), KluSettings::new());
+    assert!(matches!(err, Err(MatrixReadError::Parse { line: 3, .. })));
+}
+
+#[test]
+fn matrix_market_untrusted_sizes() {
+    // the dimension can not be indexed with i32
+    let file = "%%MatrixMarket matrix coordinate real general\n3000000000 3000000000 1\n1 1 1.0\n";
+    let err = FixedKluMatrix::<i32, f64>::read_matrix_market(file.as_bytes(), KluSettings::new());
+    assert!(matches!(
+        err,
+        Err(MatrixReadError::Klu(KluError::InvalidPattern(
+            PatternError::TooLarge { .. }
+        )))
+    ));
+
+    // huge counts are not preallocated, reading simply stops at the end of the file
+    let file = format!(
+        "%%MatrixMarket matrix coordinate real general\n2 2 {}\n1 1 1.0\n",
+        usize::MAX
+    );
+    let err = FixedKluMatrix::<i32, f64>::read_matrix_market(file.as_bytes(), KluSettings::new());
+    assert!(matches!(err, Err(MatrixReadError::Parse { line: 3, .. })));
+    // the pattern is not allocated for a huge dimension with a single entry
+    let file = "%%MatrixMarket matrix coordinate real general\n2000000000 2000000000 1\n1 1 1.0\n";
+    let err = FixedKluMatrix::<i32, f64>::read_matrix_market(file.as_bytes(), KluSettings::new());
+    assert!(matches!(err, Err(MatrixReadError::Unsupported(_))));
+    let file = format!(
+        "%%MatrixMarket matrix array real general\n{} 1\n1.0\n",
+        usize::MAX
+    );
+    let err = read_matrix_market_vector::<f64>(file.as_bytes());
+    assert!(matches!(err, Err(MatrixReadError::Parse { line: 3, .. })));
+}
+
+#[test]
+fn matrix_market_csr() {
+    let spec =
+        KluMatrixSpec::from_csr(2, vec![0, 2, 3], vec![0, 1, 1], KluSettings::new()).unwrap();
+    let matrix = spec.create_matrix::<f64>().unwrap();
+    matrix[(1, 0)].set(2.0);
+    let mut exported = Vec::new();
+    matrix.write_matrix_market(&mut exported).unwrap();
+    let exported = String::from_utf8(exported).unwrap();
+    assert!(exported.contains("\n1 2 2\n"), "{exported}");
+}
+
+#[test]
+fn rutherford_boeing() {
+    let file = "\
+Test matrix                                                             TEST
+             5             1             1             2
+rua                        3             3             4             0
+(4I3)           (4I3)           (2D10.3)
+  1  3  4  5
+  2  1  3  2
+ 1.000D+00-2.500D-01
+ 3.000D+00 4.000D+00
+";
+    let matrix =
+        FixedKluMatrix::<i32, f64>::read_rutherford_boeing(file.as_bytes(), KluSettings::new())
+            .unwrap();
+    assert_eq!(matrix[(0, 0)].get(), -0.25);
+    assert_eq!(matrix[(0, 1)].get(), 1.0);
+    assert_eq!(matrix[(1, 2)].get(), 3.0);
+    assert_eq!(matrix[(2, 1)].get(), 4.0);
+
+    let mut exported = Vec::new();
+    matrix
+        .write_rutherford_boeing(&mut exported, "Test matrix", "TEST")
+        .unwrap();
+    let reimported =
+        FixedKluMatrix::<i32, f64>::read_rutherford_boeing(&*exported, KluSettings::new()).unwrap();
+    assert_eq!(
+        &*reimported.spec().column_offsets,
+        &*matrix.spec().column_offsets
+    );
+    assert_eq!(&*reimported.spec().row_indices, &*matrix.spec().row_indices);
+    for (a, b) in matrix.data().iter().zip(reimported.data()) {
+        assert_eq!(a.get(), b.get());
+    }
+
+    let err = FixedKluMatrix::<i32, f64>::read_rutherford_boeing(
+        file.replace("rua", "rsa").as_bytes(),
+        KluSettings::new(),
+    );
+    assert!(matches!(err, Err(MatrixReadError::Unsupported(_))));
+}
+
+#[test]
+fn rutherford_boeing_untrusted_sizes() {
+    // the number of entries can not be indexed with i32
+    let file = "\
+Test matrix                                                             TEST
+             3             1             1             1
+rua                        3             3    3000000000             0
+(4I3)           (4I3)           (2D10.3)
+  1  3  4  5
+  2  1  3  2
+ 1.000D+00-2.500D-01
+ 3.000D+00 4.000D+00
+";
+    let err =
+        FixedKluMatrix::<i32, f64>::read_rutherford_boeing(file.as_bytes(), KluSettings::new());
+    assert!(matches!(
+        err,
+        Err(MatrixReadError::Klu(KluError::InvalidPattern(
+            PatternError::TooLarge { .. }
+        )))
+    ));
+
+    // huge counts are not preallocated, reading stops at the first line that is not an index
+    let err =
+        FixedKluMatrix::<i64, f64>::read_rutherford_boeing(file.as_bytes(), KluSettings::new());
+    assert!(matches!(err, Err(MatrixReadError::Parse { line: 7, .. })));
+
+    // an index that can not be represented by i32
+    let file = "\
+Test matrix                                                             TEST
+             3             1             1             1
+rua                        3             3             4             0
+(4I3)           (1I11)          (2D10.3)
+  1  3  4  5
+ 9999999999
+          1
+          3
+          2
+ 1.000D+00-2.500D-01
+ 3.000D+00 4.000D+00
+";
+    let err =
+        FixedKluMatrix::<i32, f64>::read_rutherford_boeing(file.as_bytes(), KluSettings::new());
+    assert!(matches!(err, Err(MatrixReadError::Parse { line: 6, .. })));
+}
+
+#[test]
+fn rutherford_boeing_complex() {
+    let spec = KluMatrixSpec::new(&tridiagonal_pattern(20), KluSettings::new()).unwrap();
+    let matrix = spec.clone().create_matrix::<Complex64>().unwrap();
+    for (i, entry) in matrix.data().iter().enumerate() {
+        entry.set(Complex64::new(1.0 / (i as f64 + 1.0), -1e-200 * i as f64));
+    }
+    let mut exported = Vec::new();
+    matrix
+        .write_rutherford_boeing(&mut exported, "", "")
+        .unwrap();
+    assert!(String::from_utf8_lossy(&exported).contains("cua"));
+    let reimported =
+        FixedKluMatrix::<i32, Complex64>::read_rutherford_boeing(&*exported, KluSettings::new())
+            .unwrap();
+    assert_eq!(&*reimported.spec().row_indices, &*matrix.spec().row_indices);
+    for (a, b) in matrix.data().iter().zip(reimported.data()) {
+        assert_eq!(a.get(), b.get());
+    }
+
+    let mut pattern = Vec::new();
+    spec.write_rutherford_boeing(&mut pattern, "", "").unwrap();
+    let pattern =
+        FixedKluMatrix::<i32, f64>::read_rutherford_boeing(&*pattern, KluSettings::new()).unwrap();
+    assert_eq!(&*pattern.spec().row_indices, &*matrix.spec().row_indices);
+}
+
+#[test]
+fn restore_ordering() {
+    // 2x2 diagonal blocks coupled by entries above the blocks
+    let pattern: Vec<Vec<i32>> = (0..8)
+        .map(|col| {
+            let mut rows = vec![col, col ^ 1];
+            if col >= 2 {
+                rows.push(col - 2);
+            }
+            rows
+        })
+        .collect();
+    let spec = KluMatrixSpec::new(&pattern, KluSettings::new()).unwrap();
+    let ordering = spec.export_ordering().unwrap();
+    assert_eq!(ordering.nblocks(), 4);
+
+    let mut file = Vec::new();
+    ordering.write(&mut file).unwrap();
+    let read = SymbolicOrdering::read(&*file).unwrap();
+    assert_eq!(read, ordering);
+
+    let restored = KluMatrixSpec::with_ordering(&pattern, &read, KluSettings::new()).unwrap();
+    assert_eq!(restored.export_ordering().unwrap(), ordering);
+
+    let solve = |spec: Rc<KluMatrixSpec<i32>>| {
+        let mut matrix = spec.create_matrix::<f64>().unwrap();
+        for (i, entry) in matrix.data().iter().enumerate() {
+            entry.set(i as f64 + 1.0);
+        }
+        let lu = matrix.lu_factorize(RefactorPolicy::never()).unwrap();
+        let mut rhs: Vec<_> = (0..8).map(f64::from).collect();
+        lu.solve_linear_system(&mut rhs).unwrap();
+        rhs
+    };
+    assert_eq!(solve(spec), solve(restored));
+
+    let err = KluMatrixSpec::with_ordering(&tridiagonal_pattern(8), &read, KluSettings::new());
+    assert_eq!(err.unwrap_err(), KluError::Invalid);
+
+    file[8] = 2;
+    let err = SymbolicOrdering::<i32>::read(&*file).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_roundtrip() {
+    let settings = KluSettings::new()
+        .with_tol(0.5)
+        .with_btf(false)
+        .with_ordering(KluOrdering::Colamd)
+        .with_scaling(KluScaling::Sum);
+    let spec = KluMatrixSpec::new(&tridiagonal_pattern(5), settings).unwrap();
+    let matrix = spec.clone().create_matrix::<Complex64>().unwrap();
+    for (i, entry) in matrix.data().iter().enumerate() {
+        entry.set(Complex64::new(i as f64 + 1.0, -0.5));
+    }
+
+    let json = serde_json::to_string(&matrix).unwrap();
+    let mut restored: FixedKluMatrix<i32, Complex64> = serde_json::from_str(&json).unwrap();
+    let restored_spec = restored.spec().clone();
+    assert_eq!(restored_spec.column_offsets, spec.column_offsets);
+    assert_eq!(restored_spec.row_indices, spec.row_indices);
+    assert_eq!(restored_spec.settings.tol(), 0.5);
+    assert!(!restored_spec.settings.btf());
+    assert_eq!(restored_spec.settings.ordering(), KluOrdering::Colamd);
+    assert_eq!(restored_spec.settings.scaling(), KluScaling::Sum);
+    for (a, b) in matrix.data().iter().zip(restored.data()) {
+        assert_eq!(a.get(), b.get());
+    }
+    restored.lu_factorize(RefactorPolicy::never()).unwrap();
+
+    let user_ordering = KluSettings::<i32>::new()
+        .with_user_ordering(|_: &[i32], _: &[i32], _: &mut [i32]| -> Option<usize> { None });
+    assert!(serde_json::to_string(&user_ordering).is_err());
+    let json = serde_json::to_string(&KluSettings::<i32>::new())
+        .unwrap()
+        .replace("\"tol\":0.001", "\"tol\":2.0");
+    let err = serde_json::from_str::<KluSettings<i32>>(&json).unwrap_err();
+    assert!(err.to_string().contains("tol"), "{err}");
+
+    // unsorted rows are rejected as the values would not be sorted with them
+    let json = r#"{"spec":{"dim":2,"column_offsets":[0,2,3],"row_indices":[1,0,1],
+        "transposed":false,"settings":SETTINGS},"values":[1.0,2.0,3.0]}"#
+        .replace(
+            "SETTINGS",
+            &serde_json::to_string(&KluSettings::<i32>::new()).unwrap(),
+        );
+    let err = serde_json::from_str::<FixedKluMatrix<i32, f64>>(&json).err();
+    assert!(err.is_some_and(|err| err.to_string().contains("not sorted")));
+    let json = json.replace("[1,0,1]", "[0,1,1]");
+    let restored = serde_json::from_str::<FixedKluMatrix<i32, f64>>(&json).unwrap();
+    assert_eq!(restored[(0, 1)].get(), 2.0);
+}
+
+#[test]
+#[should_panic]
+fn invalid_tol() {
+    let _ = KluSettings::<i32>::new().with_tol(1.5);
+}
+
+#[test]
+#[should_panic(expected = "min_rcond must be set on a policy that refactors")]
+fn limits_without_refactoring() {
+    let _ = RefactorPolicy::never().with_min_rcond(1e-12);
+}
+
+#[test]
+fn fallible_refactor_limits() {
+    let policy = RefactorPolicy::always();
+    for min_rcond in [-1.0, f64::INFINITY, f64::NAN] {
+        assert!(policy.try_with_min_rcond(min_rcond).is_err());
+    }
+    for max_pivot_growth in [0.5, f64::INFINITY] {
+        assert!(policy.try_with_max_pivot_growth(max_pivot_growth).is_err());
+    }
+    assert!(policy.try_with_full_factor_every(0).is_err());
+    let err = RefactorPolicy::never()
+        .try_with_full_factor_every(3)
+        .unwrap_err();
+    assert_eq!(err.setting, "full_factor_every");
+
+    let policy = policy
+        .try_with_min_rcond(0.0)
+        .and_then(|policy| policy.try_with_max_pivot_growth(1e3))
+        .and_then(|policy| policy.try_with_full_factor_every(3))
+        .unwrap();
+    assert_eq!(policy.min_rcond(), Some(0.0));
+    assert_eq!(policy.max_pivot_growth(), Some(1e3));
+    assert_eq!(policy.full_factor_every(), Some(3));
+}
+
 fn real_number() -> impl Strategy<Value = f64> + Clone {
     let vals_pos = 1e-4..1e4;
     let vals_neg = -1e4..-1e-4;
@@ -118,9 +1495,11 @@ struct LinearSystem<D> {
 impl<D: KluData> LinearSystem<D> {
     fn gen_klu_spec(&self) -> Rc<KluMatrixSpec<i32>> {
         let dim = self.rhs.len() as i32;
-        let mut builder = KluMatrixBuilder::new(dim as i32);
+        let mut builder = KluMatrixBuilder::new(dim);
         self.for_matirx_entry(0, |col, row, _| builder.add_entry(col, row));
-        builder.finish(KluSettings::new())
+        builder
+            .finish(KluSettings::new())
+            .expect("pattern is valid")
     }
 
     fn data(&self, matrix: u32) -> &[D] {
@@ -145,7 +1524,7 @@ impl<D: KluData> LinearSystem<D> {
         let mut matrix = spec.create_matrix().expect("matrix is not empty");
         let num_matricies = self.matrix_data.len() as u32 / self.matrix_len;
         for i in 0..num_matricies {
-            self.test_klu_solve(i as u32, &mut matrix)?
+            self.test_klu_solve(i, &mut matrix)?
         }
         Ok(())
     }
@@ -158,13 +1537,13 @@ impl<D: KluData> LinearSystem<D> {
         self.for_matirx_entry(matrix, |col, row, val| {
             dst[(col, row)].set(val);
         });
-        let is_singluar = dst.lu_factorize(Some(1e-12));
-        if is_singluar {
+        let lu = match dst.lu_factorize(RefactorPolicy::always().with_min_rcond(1e-12)) {
             // singular matrix... assume this is correct
-            return Ok(());
-        }
+            Err(KluError::Singular { .. }) => return Ok(()),
+            res => res.expect("factorization failed"),
+        };
         let mut solv = self.rhs.clone();
-        dst.solve_linear_system(&mut solv);
+        lu.solve_linear_system(&mut solv).expect("solve failed");
         let mut check = vec![D::zero(); solv.len()];
 
         self.for_matirx_entry(matrix, |col, row, val| {
@@ -198,7 +1577,7 @@ impl<D: KluData> LinearSystem<D> {
 
     //     let reference = self.rhs.clone();
 
-    //     matrix.lu_factorize(None);
+    //     matrix.lu_factorize(RefactorPolicy::never());
     //     let mut rhs: Vec<f64> = Vec::with_capacity(size as usize);
     //     for i in 0..size {
     //         rhs.push(rand::random());
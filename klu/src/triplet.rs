@@ -0,0 +1,132 @@
+use std::rc::Rc;
+
+use crate::{
+    FixedKluMatrix, KluData, KluError, KluIndex, KluMatrixBuilder, KluMatrixSpec, KluSettings,
+    PatternError,
+};
+
+/// Assembles a [`FixedKluMatrix`] from `(row, column, value)` triplets (COO format).
+/// Values of duplicate entries are summed.
+///
+/// The sparsity pattern is analyzed the first time triplets are assembled. As long as later
+/// calls to [`assemble`](Self::assemble) use the same `(row, column)` sequence the values are
+/// scattered directly into the existing matrix using a precomputed index map.
+/// If the sequence changes the pattern is analyzed again. The previous spec is reused for this
+/// unless it is still shared (for example through a clone of [`FixedKluMatrix::spec`]), in which
+/// case a new spec is created with a copy of the settings that shares the user ordering.
+pub struct TripletAssembler<I: KluIndex, D: KluData> {
+    dim: I,
+    /// Usually only shared with `matrix` so it can be reinitialized once the matrix is dropped.
+    /// If a clone of the spec is still alive a new spec is created instead.
+    spec: Rc<KluMatrixSpec<I>>,
+    matrix: Option<FixedKluMatrix<I, D>>,
+    /// The `(row, column)` sequence the index map was built for
+    pattern: Vec<(I, I)>,
+    /// The offset within the matrix data of each triplet
+    index_map: Vec<usize>,
+}
+
+impl<I: KluIndex, D: KluData> TripletAssembler<I, D> {
+    /// Creates an assembler for a square matrix of size `dim` that uses `klu_settings` for the
+    /// analysis and factorization.
+    pub fn new(dim: I, klu_settings: KluSettings<I>) -> Self {
+        Self {
+            dim,
+            spec: Rc::new(KluMatrixSpec::empty(klu_settings)),
+            matrix: None,
+            pattern: Vec::new(),
+            index_map: Vec::new(),
+        }
+    }
+
+    /// Writes the sum of all triplets into the matrix and returns it. All entries not present in
+    /// `triplets` are set to zero.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KluError::InvalidPattern`] if a triplet is outside of the matrix,
+    /// [`KluError::Invalid`] if `triplets` is empty and any other error if KLU fails to analyze
+    /// the sparsity pattern.
+    pub fn assemble(
+        &mut self,
+        triplets: &[(I, I, D)],
+    ) -> Result<&mut FixedKluMatrix<I, D>, KluError> {
+        let same_pattern = self.matrix.is_some()
+            && self.pattern.len() == triplets.len()
+            && self
+                .pattern
+                .iter()
+                .zip(triplets)
+                .all(|(&(row, column), &(row_, column_, _))| row == row_ && column == column_);
+        if !same_pattern {
+            self.analyze(triplets)?;
+        }
+
+        let matrix = self.matrix.as_mut().unwrap();
+        matrix.write_zero();
+        let data = matrix.data();
+        for (&offset, &(_, _, val)) in self.index_map.iter().zip(triplets) {
+            let entry = &data[offset];
+            entry.set(entry.get() + val);
+        }
+        Ok(matrix)
+    }
+
+    /// The most recently assembled matrix
+    pub fn matrix(&self) -> Option<&FixedKluMatrix<I, D>> {
+        self.matrix.as_ref()
+    }
+
+    pub fn matrix_mut(&mut self) -> Option<&mut FixedKluMatrix<I, D>> {
+        self.matrix.as_mut()
+    }
+
+    pub fn into_matrix(self) -> Option<FixedKluMatrix<I, D>> {
+        self.matrix
+    }
+
+    fn analyze(&mut self, triplets: &[(I, I, D)]) -> Result<(), KluError> {
+        // reuse the allocations of the previous matrix
+        let alloc = self
+            .matrix
+            .take()
+            .map_or_else(Vec::new, FixedKluMatrix::into_alloc);
+        self.pattern.clear();
+        self.index_map.clear();
+
+        let zero = I::from_usize(0);
+        let in_bounds = |idx: I| zero <= idx && idx < self.dim;
+        if let Some(&(row, column, _)) = triplets
+            .iter()
+            .find(|&&(row, column, _)| !in_bounds(row) || !in_bounds(column))
+        {
+            return Err(PatternError::EntryOutOfBounds {
+                column: column.into_i64(),
+                row: row.into_i64(),
+                dim: self.dim.into_usize(),
+            }
+            .into());
+        }
+
+        let mut builder = KluMatrixBuilder::new(self.dim);
+        for &(row, column, _) in triplets {
+            builder.add_entry(column, row);
+        }
+        match Rc::get_mut(&mut self.spec) {
+            Some(spec) => builder.reinit(spec)?,
+            // a clone of the previous spec is still alive (e.g. from `FixedKluMatrix::spec`)
+            None => self.spec = builder.finish(self.spec.settings.copy_for_analysis())?,
+        }
+
+        self.index_map.extend(
+            triplets
+                .iter()
+                .map(|&(row, column, _)| self.spec.offset(column, row).unwrap()),
+        );
+        self.pattern
+            .extend(triplets.iter().map(|&(row, column, _)| (row, column)));
+        let matrix = FixedKluMatrix::new_with_alloc(self.spec.clone(), alloc);
+        self.matrix = Some(matrix.ok_or(KluError::Invalid)?);
+        Ok(())
+    }
+}